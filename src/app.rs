@@ -1,27 +1,67 @@
-use std::io;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{self, KeyEventKind};
-use ratatui::{crossterm, text::{self, Text}, widgets::Widget, DefaultTerminal, Frame};
+use crossterm::event::{self, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    crossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Text},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph, Widget, Wrap},
+    DefaultTerminal, Frame,
+};
 
-use crate::{main_menu::MainMenu, menu::Menu};
+use crate::{
+    main_menu::MainMenu, menu::Menu, quit_menu::QuitMenu, recovery, recovery_menu::RecoveryMenu,
+};
+
+/// Smallest terminal the menus are drawn in, below this only a warning is
+/// shown.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 12;
+/// How often the menu on top gets ticked and the screen redrawn when nothing
+/// else happens.
+const TICK_RATE: Duration = Duration::from_millis(50);
+/// Keys that work the same in every menu, listed under the menu's own in the
+/// help overlay.
+const GLOBAL_BINDINGS: [(&str, &str); 3] = [("Esc", "Back"), ("Ctrl+Q", "Quit"), ("?, F1", "Help")];
 
 pub struct App {
     should_exit: bool,
+    resized: bool,
+    show_help: bool,
     menus: Vec<Box<dyn Menu<Output = ()>>>,
 }
 
 impl App {
     pub fn new() -> Self {
+        let mut menus: Vec<Box<dyn Menu<Output = ()>>> = vec![Box::new(MainMenu::new())];
+        if let Some(recovered) = recovery::load() {
+            menus.push(Box::new(RecoveryMenu::new(recovered)));
+        }
         Self {
             should_exit: false,
-            menus: vec![Box::new(MainMenu::new())],
+            resized: false,
+            show_help: false,
+            menus,
         }
     }
 
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let mut last_tick = Instant::now();
         while !self.should_exit {
+            if std::mem::take(&mut self.resized) {
+                terminal.clear()?;
+            }
             terminal.draw(|frame| self.draw(frame))?;
-            self.handle_input()?;
+            if event::poll(TICK_RATE.saturating_sub(last_tick.elapsed()))? {
+                self.handle_input()?;
+            }
+            if last_tick.elapsed() >= TICK_RATE {
+                self.menus.last_mut().unwrap().tick(last_tick.elapsed());
+                last_tick = Instant::now();
+            }
             if let Some(result) = self.menus.last_mut().unwrap().is_done() {
                 match result {
                     crate::menu::MenuOptions::GoBack => {
@@ -35,6 +75,13 @@ impl App {
                     crate::menu::MenuOptions::Continue(menu) => {
                         self.menus.push(menu);
                     },
+                    crate::menu::MenuOptions::Return(value) => {
+                        self.menus.pop();
+                        if let Some(menu) = self.menus.last_mut(){
+                            menu.receive(value);
+                        }
+                    }
+                    crate::menu::MenuOptions::Quit => self.ask_quit(),
                     crate::menu::MenuOptions::Exit(_) => self.should_exit = true,
                 }
             }
@@ -46,11 +93,48 @@ impl App {
         frame.render_widget(self, frame.area());
     }
 
+    /// Asks whether to quit, listing the unsaved work of every menu on the
+    /// stack.
+    fn ask_quit(&mut self) {
+        let unsaved = self
+            .menus
+            .iter()
+            .filter_map(|menu| menu.unsaved_changes())
+            .map(str::to_owned)
+            .collect();
+        self.menus.push(Box::new(QuitMenu::new(unsaved)));
+    }
+
+    /// Closes the menu on top if it agrees to, backing out of the first menu
+    /// asks to quit.
+    fn back(&mut self) {
+        if self.menus.len() == 1 {
+            self.ask_quit();
+        } else if self.menus.last_mut().unwrap().back() {
+            self.menus.pop();
+        }
+    }
+
     fn handle_input(&mut self) -> io::Result<()> {
         match event::read()? {
-            event::Event::Key(key) if key.kind == KeyEventKind::Press => {
-                self.menus.last_mut().unwrap().handle_input(key);
+            // Any key closes the help overlay and does nothing else.
+            event::Event::Key(key) if key.kind == KeyEventKind::Press && self.show_help => {
+                self.show_help = false;
             }
+            event::Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.ask_quit()
+                }
+                KeyCode::Char('?') if !self.menus.last().unwrap().captures_text() => self.show_help = true,
+                KeyCode::F(1) => self.show_help = true,
+                KeyCode::Esc => self.back(),
+                _ => self.menus.last_mut().unwrap().handle_input(key),
+            },
+            event::Event::Mouse(_) if self.show_help => {}
+            event::Event::Mouse(mouse) => {
+                self.menus.last_mut().unwrap().handle_mouse(mouse);
+            }
+            event::Event::Resize(_, _) => self.resized = true,
             _ => {}
         };
         Ok(())
@@ -68,6 +152,91 @@ impl Widget for &App {
     where
         Self: Sized,
     {
-        self.menus.last().unwrap().as_ref().render_ref(area, buf);
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            too_small(area, buf);
+            return;
+        }
+        // Everything from the last full screen menu up, popups are drawn over
+        // it in order.
+        let first = self
+            .menus
+            .iter()
+            .rposition(|menu| menu.popup().is_none())
+            .unwrap_or(0);
+        for menu in &self.menus[first..] {
+            match menu.popup() {
+                Some(popup) => {
+                    let inner = render_popup(Block::new().title(popup.title), popup.width, popup.height, area, buf);
+                    menu.render_ref(inner, buf);
+                }
+                None => menu.render_ref(area, buf),
+            }
+        }
+        if self.show_help {
+            help(self.menus.last().unwrap().bindings(), area, buf);
+        }
     }
 }
+
+/// The keys of the menu on top and the global ones, in a box over the menu.
+fn help(bindings: &[(&str, &str)], area: Rect, buf: &mut ratatui::prelude::Buffer) {
+    let key_width = bindings
+        .iter()
+        .chain(&GLOBAL_BINDINGS)
+        .map(|(key, _)| key.len())
+        .max()
+        .unwrap_or(0);
+    let line = |(key, action): &(&str, &str)| Line::from(format!("{:>key_width$}  {}", key, action));
+    let mut lines: Vec<Line> = bindings.iter().map(line).collect();
+    if !lines.is_empty() {
+        lines.push(Line::from(""));
+    }
+    lines.extend(GLOBAL_BINDINGS.iter().map(line));
+    let text = Text::from(lines);
+    let inner = render_popup(
+        Block::new().title("Help").title_bottom("any key to close"),
+        text.width() as u16,
+        text.height() as u16,
+        area,
+        buf,
+    );
+    text.render(inner, buf);
+}
+
+/// Clears a box in the middle of `area` with room for `width` by `height`
+/// inside, draws `block` around it and returns the inside.
+fn render_popup(block: Block, width: u16, height: u16, area: Rect, buf: &mut ratatui::prelude::Buffer) -> Rect {
+    let block = block
+        .borders(ratatui::widgets::Borders::ALL)
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Rounded);
+    let [popup] = Layout::vertical([Constraint::Length(height.saturating_add(2))])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Length(width.saturating_add(4))])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+    let inner = block.inner(popup);
+    block.render(popup, buf);
+    inner
+}
+
+fn too_small(area: Rect, buf: &mut ratatui::prelude::Buffer) {
+    let text = Text::from(vec![
+        "Terminal too small".into(),
+        format!("{}x{}, needs {}x{}", area.width, area.height, MIN_WIDTH, MIN_HEIGHT).into(),
+    ]);
+    let top = area.height.saturating_sub(text.height() as u16) / 2;
+    Paragraph::new(text)
+        .centered()
+        .wrap(Wrap { trim: true })
+        .render(
+            Rect {
+                y: area.y + top,
+                height: area.height - top,
+                ..area
+            },
+            buf,
+        );
+}
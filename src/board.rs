@@ -1,80 +1,868 @@
-use std::ops::{Add, Sub};
+use std::ops::Add;
 
-use ratatui::{buffer::Buffer, layout::{Position, Rect}, widgets::StatefulWidget};
+use ratatui::{buffer::Buffer, layout::{Position, Rect}, style::Style, widgets::StatefulWidget};
+use serde::Deserializer;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::tiles::Tile;
+use crate::{theme, tiles::{BoxColor, Tile, TileDescriptor}};
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Longest a row of a saved map can get, and the largest side the editor
+/// resizes maps to.
+pub const MAX_SIZE: usize = 100;
+
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "BoardRepr", into = "BoardRepr")]
 pub struct Board{
     width: usize,
     tiles: Box<[Tile]>,
-    player_pos: Pos,
-    goal: Pos,
+    players: Vec<Pos>,
+    goals: Vec<Pos>,
+    targets: Vec<Target>,
+    win_condition: WinCondition,
+    movement: Movement,
+}
+
+/// A spot a box has to be pushed onto. Colored targets only accept a box of
+/// the same color, plain ones only accept uncolored boxes.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Target{
+    pub pos: Pos,
+    pub color: Option<BoxColor>,
+}
+
+/// What has to happen for a level to count as solved.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WinCondition{
+    /// A single player standing on a goal is enough.
+    #[default]
+    AnyPlayer,
+    /// Every player has to stand on a goal at the same time.
+    AllPlayers,
+}
+
+/// How players move boxes around in a level.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Movement{
+    /// Walking into a box pushes it ahead.
+    #[default]
+    Push,
+    /// Walking away from a box drags it along behind, boxes can't be pushed.
+    Pull,
+}
+
+impl Movement{
+    pub fn reversed(self) -> Self{
+        match self{
+            Movement::Push => Movement::Pull,
+            Movement::Pull => Movement::Push,
+        }
+    }
+}
+
+/// Why a board couldn't be made or loaded.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BoardError{
+    /// A side of the board is zero tiles long.
+    Empty,
+    /// The tiles don't fill a whole number of rows.
+    Ragged{tiles: usize, width: usize},
+    /// A row of a saved map isn't as long as the first one.
+    RowLength{row: usize, len: usize, width: usize},
+    UnknownTile(char),
+    /// A row of a saved map ends in a count without a tile after it.
+    DanglingCount{row: usize},
+    /// A row is longer than [`MAX_SIZE`], in a saved map most likely from a
+    /// count that's far too big.
+    RowTooLong{row: usize},
+    NoPlayers,
+    /// A player, goal or target off the edge of the board.
+    OutOfBounds{what: &'static str, pos: Pos},
+}
+
+impl std::fmt::Display for BoardError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self{
+            BoardError::Empty => write!(f, "the map has no tiles"),
+            BoardError::Ragged{tiles, width} => write!(f, "{} tiles don't make rows of {}", tiles, width),
+            BoardError::RowLength{row, len, width} => write!(f, "row {} is {} tiles long instead of {}", row, len, width),
+            BoardError::UnknownTile(c) => write!(f, "unknown tile '{}'", c),
+            BoardError::DanglingCount{row} => write!(f, "row {} ends in a count without a tile", row),
+            BoardError::RowTooLong{row} => write!(f, "row {} is longer than {} tiles", row, MAX_SIZE),
+            BoardError::NoPlayers => write!(f, "the map has no players"),
+            BoardError::OutOfBounds{what, pos} => write!(f, "the {} at ({}, {}) is off the map", what, pos.0, pos.1),
+        }
+    }
+}
+
+impl std::error::Error for BoardError{}
+
+/// The shape a [`Board`] is saved as. The tiles are written as one string per
+/// row, with runs of three or more of the same tile shortened to the count
+/// followed by the tile, so `"#---*--#"` can also be `"#3-*2-#"`.
+#[derive(Serialize, Deserialize)]
+struct BoardRepr{
+    rows: Vec<String>,
+    players: Vec<Pos>,
+    goals: Vec<Pos>,
+    #[serde(default)]
+    targets: Vec<Target>,
+    #[serde(default)]
+    win_condition: WinCondition,
+    #[serde(default)]
+    movement: Movement,
+}
+
+/// How boards were saved in level files before version 2, with `width` and
+/// a flat `tiles` list instead of rows. Single player maps from before that
+/// have `player_pos` and `goal` instead of `players` and `goals`. Only read,
+/// to migrate old files.
+#[derive(Deserialize)]
+pub struct LegacyBoard{
+    width: usize,
+    tiles: Box<[Tile]>,
+    #[serde(default)]
+    players: Vec<Pos>,
+    #[serde(default, deserialize_with = "some")]
+    player_pos: Option<Pos>,
+    #[serde(default)]
+    goals: Vec<Pos>,
+    #[serde(default, deserialize_with = "some")]
+    goal: Option<Pos>,
+    #[serde(default)]
+    targets: Vec<Target>,
+    #[serde(default)]
+    win_condition: WinCondition,
+    #[serde(default)]
+    movement: Movement,
 }
 
+/// Lets optional fields be written without `Some(..)`, the way old maps
+/// have them.
+fn some<'de, D: Deserializer<'de>, T: serde::Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error>{
+    T::deserialize(deserializer).map(Some)
+}
 
-#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+fn encode_row(row: &[Tile]) -> String{
+    let mut encoded = String::new();
+    let mut rest = row;
+    while let Some(&tile) = rest.first(){
+        let run = rest.iter().take_while(|other| **other == tile).count();
+        let glyph = char::from(tile);
+        if run >= 3{
+            encoded.push_str(&run.to_string());
+            encoded.push(glyph);
+        }else{
+            encoded.extend(std::iter::repeat_n(glyph, run));
+        }
+        rest = &rest[run..];
+    }
+    encoded
+}
+
+/// The tiles of the `index`th row of a saved map.
+fn decode_row(index: usize, row: &str) -> Result<Vec<Tile>, BoardError>{
+    let mut tiles = Vec::new();
+    let mut count: Option<usize> = None;
+    for c in row.chars(){
+        if let Some(digit) = c.to_digit(10){
+            count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+            continue;
+        }
+        let tile = Tile::try_from(c).map_err(BoardError::UnknownTile)?;
+        let run = count.take().unwrap_or(1);
+        if run > MAX_SIZE - tiles.len(){
+            return Err(BoardError::RowTooLong{row: index});
+        }
+        tiles.extend(std::iter::repeat_n(tile, run));
+    }
+    match count{
+        Some(_) => Err(BoardError::DanglingCount{row: index}),
+        None => Ok(tiles),
+    }
+}
+
+impl TryFrom<BoardRepr> for Board{
+    type Error = BoardError;
+
+    fn try_from(value: BoardRepr) -> Result<Self, Self::Error> {
+        let rows = value.rows.iter().enumerate().map(|(i, row)| decode_row(i, row)).collect::<Result<Vec<_>, _>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some((row, tiles)) = rows.iter().enumerate().find(|(_, row)| row.len() != width){
+            return Err(BoardError::RowLength{row, len: tiles.len(), width});
+        }
+        let board = Self{
+            width,
+            tiles: rows.concat().into_boxed_slice(),
+            players: value.players,
+            goals: value.goals,
+            targets: value.targets,
+            win_condition: value.win_condition,
+            movement: value.movement,
+        };
+        board.validate()?;
+        Ok(board)
+    }
+}
+
+impl TryFrom<LegacyBoard> for Board{
+    type Error = BoardError;
+
+    fn try_from(value: LegacyBoard) -> Result<Self, Self::Error> {
+        let mut players = value.players;
+        players.extend(value.player_pos);
+        let mut goals = value.goals;
+        goals.extend(value.goal);
+        let board = Self{
+            width: value.width,
+            tiles: value.tiles,
+            players,
+            goals,
+            targets: value.targets,
+            win_condition: value.win_condition,
+            movement: value.movement,
+        };
+        board.validate()?;
+        Ok(board)
+    }
+}
+
+impl From<Board> for BoardRepr{
+    fn from(value: Board) -> Self {
+        Self{
+            rows: value.rows().map(encode_row).collect(),
+            players: value.players,
+            goals: value.goals,
+            targets: value.targets,
+            win_condition: value.win_condition,
+            movement: value.movement,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Pos(pub usize, pub usize);
 
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Direction{
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction{
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    pub fn opposite(self) -> Self{
+        match self{
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// Rotations are clockwise, flips mirror the board along the named axis.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Transform{
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl Transform{
+    /// Where `pos` ends up on a board of the given size.
+    fn apply(self, pos: Pos, width: usize, height: usize) -> Pos{
+        match self{
+            Transform::Rotate90 => Pos(height - 1 - pos.1, pos.0),
+            Transform::Rotate180 => Pos(width - 1 - pos.0, height - 1 - pos.1),
+            Transform::Rotate270 => Pos(pos.1, width - 1 - pos.0),
+            Transform::FlipHorizontal => Pos(width - 1 - pos.0, pos.1),
+            Transform::FlipVertical => Pos(pos.0, height - 1 - pos.1),
+        }
+    }
+
+    fn swaps_sides(self) -> bool{
+        matches!(self, Transform::Rotate90 | Transform::Rotate270)
+    }
+}
+
 impl Board{
-    pub fn new(width: usize, height: usize) -> Self{
-        Self{
+    /// An empty board with the player in the top left corner and the goal in
+    /// the bottom right one.
+    pub fn try_new(width: usize, height: usize) -> Result<Self, BoardError>{
+        if width == 0 || height == 0{
+            return Err(BoardError::Empty);
+        }
+        if width > MAX_SIZE{
+            return Err(BoardError::RowTooLong{row: 0});
+        }
+        Ok(Self{
             width,
             tiles: (0..).take(width*height).map(|_|{Tile::default()}).collect(),
-            player_pos: Pos(0, 0),
-            goal: Pos(width-1, height-1),
+            players: vec![Pos(0, 0)],
+            goals: vec![Pos(width-1, height-1)],
+            targets: Vec::new(),
+            win_condition: WinCondition::default(),
+            movement: Movement::default(),
+        })
+    }
+
+    /// Like [`Board::try_new`], for sizes that are known to be fine. Panics
+    /// if a side is zero.
+    pub fn new(width: usize, height: usize) -> Self{
+        Self::try_new(width, height).expect("board sides are at least one tile")
+    }
+
+    /// Checks what deserializing can't, that the tiles make whole rows and
+    /// everything on the board is actually on it.
+    pub fn validate(&self) -> Result<(), BoardError>{
+        if self.width == 0 || self.tiles.is_empty(){
+            return Err(BoardError::Empty);
+        }
+        if !self.tiles.len().is_multiple_of(self.width){
+            return Err(BoardError::Ragged{tiles: self.tiles.len(), width: self.width});
+        }
+        if self.width > MAX_SIZE{
+            return Err(BoardError::RowTooLong{row: 0});
+        }
+        if self.players.is_empty(){
+            return Err(BoardError::NoPlayers);
+        }
+        let inside = |pos: &Pos| pos.0 < self.width && pos.1 < self.height();
+        let positions = self.players.iter().map(|pos| ("player", pos))
+            .chain(self.goals.iter().map(|pos| ("goal", pos)))
+            .chain(self.targets.iter().map(|target| ("target", &target.pos)));
+        for (what, pos) in positions{
+            if !inside(pos){
+                return Err(BoardError::OutOfBounds{what, pos: *pos});
+            }
+        }
+        Ok(())
+    }
+
+    /// The rectangle between two corners, given in any order, as its top left
+    /// corner and its size, cut down to fit on the board.
+    pub fn clamp_rect(&self, a: Pos, b: Pos) -> (Pos, Pos){
+        let top_left = Pos(a.0.min(b.0), a.1.min(b.1));
+        let end = Pos((a.0.max(b.0) + 1).min(self.width), (a.1.max(b.1) + 1).min(self.height()));
+        (top_left, Pos(end.0.saturating_sub(top_left.0), end.1.saturating_sub(top_left.1)))
+    }
+
+    /// The board rotated or flipped, with players, goals and targets moved
+    /// along. None of the tiles have a direction, so they are kept as is.
+    pub fn transformed(&self, transform: Transform) -> Board{
+        let (width, height) = (self.width, self.height());
+        let new_width = if transform.swaps_sides() { height } else { width };
+        let mut tiles = vec![Tile::Empty; self.tiles.len()];
+        for (i, tile) in self.tiles.iter().enumerate(){
+            let pos = transform.apply(Pos(i % width, i / width), width, height);
+            tiles[pos.1 * new_width + pos.0] = *tile;
+        }
+        let apply = |pos: &Pos| transform.apply(*pos, width, height);
+        Board{
+            width: new_width,
+            tiles: tiles.into(),
+            players: self.players.iter().map(apply).collect(),
+            goals: self.goals.iter().map(apply).collect(),
+            targets: self
+                .targets
+                .iter()
+                .map(|target| Target{ pos: apply(&target.pos), ..*target })
+                .collect(),
+            win_condition: self.win_condition,
+            movement: self.movement,
+        }
+    }
+
+    /// The same level in a fixed orientation, so levels that only differ by
+    /// a rotation or a flip come out equal.
+    pub fn normalized(&self) -> Board{
+        let mirrored = self.transformed(Transform::FlipHorizontal);
+        [self.clone(), mirrored]
+            .into_iter()
+            .flat_map(|board| {
+                [
+                    board.transformed(Transform::Rotate90),
+                    board.transformed(Transform::Rotate180),
+                    board.transformed(Transform::Rotate270),
+                    board,
+                ]
+            })
+            .map(|mut board| {
+                board.players.sort_by_key(|pos| (pos.1, pos.0));
+                board.goals.sort_by_key(|pos| (pos.1, pos.0));
+                board.targets.sort_by_key(|target| (target.pos.1, target.pos.0));
+                (ron::to_string(&board).unwrap_or_default(), board)
+            })
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, board)| board)
+            .expect("there are always eight orientations")
+    }
+
+    /// A copy of the rectangle between two corners as a board of its own,
+    /// with the targets in it. Players and goals are left out.
+    pub fn region(&self, a: Pos, b: Pos) -> Board{
+        let (top_left, size) = self.clamp_rect(a, b);
+        Board{
+            width: size.0,
+            tiles: (0..size.1)
+                .flat_map(|y| (0..size.0).map(move |x| Pos(x, y)))
+                .map(|pos| *self.get_tile(pos + top_left).expect("region is clamped to the board"))
+                .collect(),
+            players: Vec::new(),
+            goals: Vec::new(),
+            targets: self
+                .targets
+                .iter()
+                .filter(|target| Self::in_rect(target.pos, top_left, size))
+                .map(|target| Target{ pos: target.pos.checked_sub(top_left).expect("target is in the region"), ..*target })
+                .collect(),
+            win_condition: self.win_condition,
+            movement: self.movement,
         }
     }
 
+    /// The board grown or cut to a new size from the top left corner. Players
+    /// and goals that end up off the board are dropped, falling back to the
+    /// ones a new board starts with if none are left.
+    pub fn resized(&self, width: usize, height: usize) -> Result<Board, BoardError>{
+        let mut board = Self::try_new(width, height)?;
+        board.paste(Pos(0, 0), self);
+        let players: Vec<Pos> = self.players.iter().copied().filter(|&pos| board.contains(pos)).collect();
+        if !players.is_empty(){
+            board.players = players;
+        }
+        let goals: Vec<Pos> = self.goals.iter().copied().filter(|&pos| board.contains(pos)).collect();
+        if !goals.is_empty() || self.goals.is_empty(){
+            board.goals = goals;
+        }
+        board.win_condition = self.win_condition;
+        board.movement = self.movement;
+        Ok(board)
+    }
+
+    fn in_rect(pos: Pos, top_left: Pos, size: Pos) -> bool{
+        pos.0 >= top_left.0 && pos.1 >= top_left.1 && pos.0 < top_left.0 + size.0 && pos.1 < top_left.1 + size.1
+    }
+
+    /// Sets every tile in the rectangle between two corners.
+    pub fn fill_rect(&mut self, a: Pos, b: Pos, tile: Tile){
+        let (top_left, size) = self.clamp_rect(a, b);
+        for y in 0..size.1{
+            for x in 0..size.0{
+                self.set_tile(Pos(x, y) + top_left, tile);
+            }
+        }
+    }
+
+    /// Empties the rectangle between two corners, targets included.
+    pub fn clear_rect(&mut self, a: Pos, b: Pos){
+        let (top_left, size) = self.clamp_rect(a, b);
+        self.fill_rect(a, b, Tile::Empty);
+        self.targets.retain(|target| !Self::in_rect(target.pos, top_left, size));
+    }
+
+    /// Copies `region` onto the board with its top left corner at `at`. The
+    /// parts hanging off the board are dropped.
+    pub fn paste(&mut self, at: Pos, region: &Board){
+        let (top_left, size) = self.clamp_rect(at, at + Pos(region.width.saturating_sub(1), region.height().saturating_sub(1)));
+        self.targets.retain(|target| !Self::in_rect(target.pos, top_left, size));
+        for y in 0..size.1{
+            for x in 0..size.0{
+                if let Some(&tile) = region.get_tile(Pos(x, y)){
+                    self.set_tile(Pos(x, y) + at, tile);
+                }
+            }
+        }
+        self.targets.extend(
+            region
+                .targets
+                .iter()
+                .filter(|target| Self::in_rect(target.pos, Pos(0, 0), size))
+                .map(|target| Target{ pos: target.pos + at, ..*target }),
+        );
+    }
+
     pub fn height(&self) -> usize{
-        self.tiles.len()/self.width
+        self.tiles.len().checked_div(self.width).unwrap_or(0)
     }
 
     pub fn width(&self) -> usize{
         self.width
     }
 
-    fn pos_to_i(&self, pos: Pos) -> usize{
-        pos.1 * self.width + pos.0
+    pub fn contains(&self, pos: Pos) -> bool{
+        pos.0 < self.width && pos.1 < self.height()
+    }
+
+    /// Where `pos` is in `tiles`, `None` if it's off the board rather than
+    /// wrapping around to another row.
+    fn pos_to_i(&self, pos: Pos) -> Option<usize>{
+        self.contains(pos).then(|| pos.1 * self.width + pos.0)
     }
 
     pub fn get_tile(&self, pos: Pos) -> Option<&Tile>{
-        self.tiles.get(self.pos_to_i(pos))
+        self.tiles.get(self.pos_to_i(pos)?)
     }
 
     pub fn get_tile_mut(&mut self, pos: Pos) -> Option<&mut Tile>{
-        let index = self.pos_to_i(pos);
+        let index = self.pos_to_i(pos)?;
         self.tiles.get_mut(index)
     }
 
+    /// The tiles one row at a time, from the top.
+    pub fn rows(&self) -> impl Iterator<Item = &[Tile]>{
+        self.tiles.chunks(self.width.max(1))
+    }
+
     pub fn set_tile(&mut self, pos: Pos, tile: Tile) {
-        let index = self.pos_to_i(pos);
-        self.tiles.get_mut(index).map(|tile_| *tile_ = tile);
+        if let Some(tile_) = self.get_tile_mut(pos) {
+            *tile_ = tile;
+        }
+    }
+
+    pub fn players(&self) -> &[Pos] {
+        &self.players
+    }
+
+    pub fn player_at(&self, pos: Pos) -> Option<usize> {
+        self.players.iter().position(|player| *player == pos)
+    }
+
+    /// Adds a player at `pos`, or removes the one standing there. The last
+    /// player can't be removed.
+    pub fn toggle_player(&mut self, pos: Pos) {
+        match self.player_at(pos) {
+            Some(i) if self.players.len() > 1 => {
+                self.players.remove(i);
+            }
+            Some(_) => {}
+            None => self.players.push(pos),
+        }
+    }
+
+    pub fn set_players(&mut self, players: Vec<Pos>) {
+        self.players = players;
     }
 
-    pub fn player_pos(&self) -> &Pos {
-        &self.player_pos
+    pub fn goals(&self) -> &[Pos] {
+        &self.goals
     }
 
-    pub fn player_pos_mut(&mut self) -> &mut Pos {
-        &mut self.player_pos
+    /// Adds a goal at `pos`, or removes the one there. The last goal can't be
+    /// removed.
+    pub fn toggle_goal(&mut self, pos: Pos) {
+        match self.goals.iter().position(|goal| *goal == pos) {
+            Some(i) if self.goals.len() > 1 => {
+                self.goals.remove(i);
+            }
+            Some(_) => {}
+            None => self.goals.push(pos),
+        }
+    }
+
+    pub fn set_goals(&mut self, goals: Vec<Pos>) {
+        self.goals = goals;
+    }
+
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    /// Adds a target of the given color at `pos`. A target of the same color
+    /// already there is removed, one of another color is recolored.
+    pub fn toggle_target(&mut self, pos: Pos, color: Option<BoxColor>) {
+        match self.targets.iter().position(|target| target.pos == pos) {
+            Some(i) if self.targets[i].color == color => {
+                self.targets.remove(i);
+            }
+            Some(i) => self.targets[i].color = color,
+            None => self.targets.push(Target { pos, color }),
+        }
+    }
+
+    /// Whether the tile on the target is a box that counts for it.
+    pub fn is_target_filled(&self, target: &Target) -> bool {
+        self.get_tile(target.pos).is_some_and(|tile| {
+            let descriptor: &TileDescriptor = tile.into();
+            descriptor.is_box() && descriptor.color() == target.color
+        })
+    }
+
+    pub fn win_condition(&self) -> WinCondition {
+        self.win_condition
+    }
+
+    pub fn set_win_condition(&mut self, win_condition: WinCondition) {
+        self.win_condition = win_condition;
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    pub fn set_movement(&mut self, movement: Movement) {
+        self.movement = movement;
+    }
+
+    /// Whether both boards have the same tiles, ignoring players and markers.
+    pub fn same_tiles(&self, other: &Board) -> bool {
+        self.width == other.width && self.tiles == other.tiles
     }
 
-    pub fn set_player_pos(&mut self, player_pos: Pos) {
-        self.player_pos = player_pos;
+    /// The box on `pos`, if there is one.
+    pub fn box_at(&self, pos: Pos) -> Option<Tile> {
+        self.get_tile(pos).copied().filter(|tile| <&TileDescriptor>::from(tile).is_box())
     }
 
-    pub fn goal(&self) -> &Pos {
-        &self.goal
+    /// Every box on the board with where it is, row by row.
+    pub fn boxes(&self) -> impl Iterator<Item = (Pos, Tile)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| <&TileDescriptor>::from(*tile).is_box())
+            .map(|(i, tile)| (Pos(i % self.width, i / self.width), *tile))
     }
 
-    pub fn goal_mut(&mut self) -> &mut Pos {
-        &mut self.goal
+    /// The board with every box moved onto a target that accepts it, or
+    /// `None` if the boxes can't all be placed. Used as the starting point
+    /// for playing a level in reverse.
+    pub fn solved(&self) -> Option<Board> {
+        let mut boxes: Vec<Tile> = self
+            .tiles
+            .iter()
+            .filter(|tile| <&TileDescriptor>::from(*tile).is_box())
+            .copied()
+            .collect();
+        if boxes.len() != self.targets.len() {
+            return None;
+        }
+        let mut solved = self.clone();
+        solved.tiles.iter_mut().for_each(|tile| {
+            if <&TileDescriptor>::from(&*tile).is_box() {
+                *tile = Tile::Empty;
+            }
+        });
+        for target in &self.targets {
+            if !matches!(solved.get_tile(target.pos), Some(Tile::Empty)) || solved.player_at(target.pos).is_some() {
+                return None;
+            }
+            let i = boxes
+                .iter()
+                .position(|tile| <&TileDescriptor>::from(tile).color() == target.color)?;
+            solved.set_tile(target.pos, boxes.swap_remove(i));
+        }
+        Some(solved)
     }
 
-    pub fn set_goal(&mut self, goal: Pos) {
-        self.goal = goal;
+    /// Every target has to be filled, and the players have to satisfy the
+    /// [`WinCondition`].
+    pub fn is_won(&self) -> bool {
+        let on_goal = |player: &Pos| self.goals.contains(player);
+        self.targets.iter().all(|target| self.is_target_filled(target))
+            && match self.win_condition {
+                WinCondition::AnyPlayer => self.players.iter().any(on_goal),
+                WinCondition::AllPlayers => self.players.iter().all(on_goal),
+            }
+    }
+
+    pub fn neighbor(&self, pos: Pos, direction: Direction) -> Option<Pos> {
+        pos.step(direction).filter(|next| self.contains(*next))
+    }
+
+    /// The positions next to `pos` that are on the board, with the direction
+    /// they're in.
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = (Direction, Pos)> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| Some((direction, self.neighbor(pos, direction)?)))
+    }
+
+    /// Every position connected to `pos` through tiles of the same kind.
+    pub fn flood_region(&self, pos: Pos) -> Vec<Pos> {
+        let Some(&tile) = self.get_tile(pos) else {
+            return Vec::new();
+        };
+        let mut region = vec![pos];
+        let mut seen = vec![false; self.tiles.len()];
+        seen[self.pos_to_i(pos).expect("pos has a tile")] = true;
+        let mut i = 0;
+        while let Some(&current) = region.get(i) {
+            for (_, next) in self.neighbors(current) {
+                if let Some(index) = self.pos_to_i(next).filter(|index| !seen[*index] && self.tiles[*index] == tile) {
+                    seen[index] = true;
+                    region.push(next);
+                }
+            }
+            i += 1;
+        }
+        region
+    }
+
+    /// Everything the players could walk to if the boxes were out of the way.
+    fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.tiles.len()];
+        let mut queue: Vec<Pos> = self.players.clone();
+        while let Some(pos) = queue.pop() {
+            let (Some(index), Some(tile)) = (self.pos_to_i(pos), self.get_tile(pos)) else {
+                continue;
+            };
+            let descriptor: &TileDescriptor = tile.into();
+            if reachable[index] || (descriptor.is_solid() && !descriptor.is_pushable()) {
+                continue;
+            }
+            reachable[index] = true;
+            queue.extend(self.neighbors(pos).map(|(_, next)| next));
+        }
+        reachable
+    }
+
+    /// The board with `before` more columns and rows of `tile` on the left
+    /// and top, and `after` more on the right and bottom, with everything on
+    /// it moved along.
+    fn padded(&self, before: Pos, after: Pos, tile: Tile) -> Board{
+        let width = self.width + before.0 + after.0;
+        let height = self.height() + before.1 + after.1;
+        let mut board = Board{
+            width,
+            tiles: vec![tile; width * height].into_boxed_slice(),
+            players: self.players.iter().map(|&pos| pos + before).collect(),
+            goals: self.goals.iter().map(|&pos| pos + before).collect(),
+            targets: self.targets.iter().map(|target| Target{ pos: target.pos + before, ..*target }).collect(),
+            win_condition: self.win_condition,
+            movement: self.movement,
+        };
+        for (y, row) in self.rows().enumerate(){
+            for (x, &tile) in row.iter().enumerate(){
+                board.set_tile(Pos(x, y) + before, tile);
+            }
+        }
+        board
+    }
+
+    /// Closes the area the players can reach off with a ring of walls and
+    /// clears out everything beyond it, targets and goals included. Where the
+    /// area runs into the edge of the board, the board grows by a tile there
+    /// to make room for the wall, or if it can't grow any more that edge is
+    /// walled up instead, except under players and goals. The last goal is
+    /// kept even when it can't be reached.
+    pub fn auto_wall(&mut self) {
+        let reachable = self.reachable();
+        let (width, height) = (self.width, self.height());
+        let touches = |edge: &dyn Fn(Pos) -> bool| {
+            reachable.iter().enumerate().any(|(i, reached)| *reached && edge(Pos(i % width, i / width)))
+        };
+        let left = touches(&|pos| pos.0 == 0);
+        let right = touches(&|pos| pos.0 == width - 1);
+        let top = touches(&|pos| pos.1 == 0);
+        let bottom = touches(&|pos| pos.1 == height - 1);
+        let grow_x = width + usize::from(left) + usize::from(right) <= MAX_SIZE;
+        let grow_y = height + usize::from(top) + usize::from(bottom) <= MAX_SIZE;
+        for (i, reached) in reachable.iter().enumerate(){
+            let pos = Pos(i % width, i / width);
+            let on_edge = (!grow_x && (pos.0 == 0 || pos.0 == width - 1)) || (!grow_y && (pos.1 == 0 || pos.1 == height - 1));
+            if *reached && on_edge && self.player_at(pos).is_none() && !self.goals.contains(&pos){
+                self.set_tile(pos, Tile::Wall);
+            }
+        }
+        let before = Pos(usize::from(grow_x && left), usize::from(grow_y && top));
+        let after = Pos(usize::from(grow_x && right), usize::from(grow_y && bottom));
+        if before != Pos(0, 0) || after != Pos(0, 0){
+            *self = self.padded(before, after, Tile::Wall);
+        }
+
+        let reachable = self.reachable();
+        let (width, height) = (self.width, self.height());
+        let touches_reachable = |pos: Pos| {
+            (pos.1.saturating_sub(1)..=(pos.1 + 1).min(height - 1)).any(|y| {
+                (pos.0.saturating_sub(1)..=(pos.0 + 1).min(width - 1)).any(|x| reachable[y * width + x])
+            })
+        };
+        for (i, tile) in self.tiles.iter_mut().enumerate() {
+            if !reachable[i] {
+                *tile = if touches_reachable(Pos(i % width, i / width)) { Tile::Wall } else { Tile::Empty };
+            }
+        }
+        let inside = |pos: &Pos| reachable[pos.1 * width + pos.0];
+        self.targets.retain(|target| inside(&target.pos));
+        let goals: Vec<Pos> = self.goals.iter().copied().filter(inside).collect();
+        if !goals.is_empty() {
+            self.goals = goals;
+        }
+    }
+
+    /// Tries to clear `pos` by pushing whatever is on it one step further.
+    /// Chaining boxes push the box in front of them along, heavy boxes can't
+    /// be pushed by another box. Nothing moves unless the whole push works.
+    fn push(&mut self, pos: Pos, direction: Direction, by_box: bool) -> bool {
+        if self.player_at(pos).is_some() {
+            return false;
+        }
+        let Some(&tile) = self.get_tile(pos) else {
+            return false;
+        };
+        let descriptor: &TileDescriptor = (&tile).into();
+        if !descriptor.is_solid() {
+            return true;
+        }
+        if !descriptor.is_pushable() || (by_box && descriptor.is_heavy()) {
+            return false;
+        }
+        let Some(behind) = self.neighbor(pos, direction) else {
+            return false;
+        };
+        let free = match self.get_tile(behind) {
+            Some(behind_tile) => {
+                !<&TileDescriptor>::from(behind_tile).is_solid() && self.player_at(behind).is_none()
+            }
+            None => false,
+        };
+        if free || (descriptor.chains() && self.push(behind, direction, true)) {
+            self.set_tile(behind, tile);
+            self.set_tile(pos, Tile::Empty);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the given player one step. Depending on the level's
+    /// [`Movement`] the boxes in front of them are pushed, or the box behind
+    /// them is pulled along. Other players block the way like walls. Returns
+    /// whether anything moved.
+    pub fn try_move(&mut self, player: usize, direction: Direction) -> bool {
+        let Some(&from) = self.players.get(player) else {
+            return false;
+        };
+        let Some(to) = self.neighbor(from, direction) else {
+            return false;
+        };
+        match self.movement {
+            Movement::Push => {
+                if !self.push(to, direction, false) {
+                    return false;
+                }
+            }
+            Movement::Pull => {
+                let free = self.player_at(to).is_none()
+                    && self.get_tile(to).is_some_and(|tile| !<&TileDescriptor>::from(tile).is_solid());
+                if !free {
+                    return false;
+                }
+                if let Some(behind) = self.neighbor(from, direction.opposite()) {
+                    if let Some(&tile) = self.get_tile(behind).filter(|tile| <&TileDescriptor>::from(*tile).is_box()) {
+                        self.set_tile(from, tile);
+                        self.set_tile(behind, Tile::Empty);
+                    }
+                }
+            }
+        }
+        self.players[player] = to;
+        true
     }
 }
 
@@ -83,25 +871,79 @@ impl StatefulWidget for &Board{
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let offset = *state;
-        let pos = Pos(area.x as usize, area.y as usize);
-        (offset.0..self.width()).for_each(|x|{
-            (offset.1..self.height()).for_each(|y|{
-                if let Some(cell) = buf.cell_mut(Pos(x, y) - offset + pos){
-                    cell.set_bg(ratatui::style::Color::DarkGray);
-                    cell.set_symbol(self.get_tile(Pos(x, y)).expect("looping through the indices of tile").into());
+        let theme = theme::current();
+        let screen = |pos: Pos| pos.to_screen(offset, area, theme.tile_width);
+        for (y, row) in self.rows().enumerate().skip(offset.1){
+            for (x, tile) in row.iter().enumerate().skip(offset.0){
+                if let Some(tile_area) = screen(Pos(x, y)){
+                    theme.empty.apply(buf, tile_area);
+                    theme.tile(tile).apply(buf, tile_area);
                 }
-            });
-        });
-        if let Some(cell) = buf.cell_mut(self.player_pos - offset + pos){
-            const PLAYER_CHAR: &str = "@";
-            cell.set_symbol(PLAYER_CHAR);
+            }
+        }
+        for target in &self.targets{
+            if let Some(tile_area) = screen(target.pos){
+                if matches!(self.get_tile(target.pos), Some(Tile::Empty)){
+                    theme.target(target.color).apply(buf, tile_area);
+                }
+                buf.set_style(tile_area, Style::new().add_modifier(theme.target_modifier));
+            }
+        }
+        for player in &self.players{
+            if let Some(tile_area) = screen(*player){
+                theme.player.apply(buf, tile_area);
+            }
         }
-        if let Some(cell) = buf.cell_mut(self.goal - offset + pos){
-            cell.set_bg(ratatui::style::Color::Green);
+        for goal in &self.goals{
+            if let Some(tile_area) = screen(*goal){
+                theme.goal.apply(buf, tile_area);
+            }
         }
     }
 }
 
+impl Pos{
+    pub fn checked_sub(self, rhs: Pos) -> Option<Pos>{
+        Some(Pos(self.0.checked_sub(rhs.0)?, self.1.checked_sub(rhs.1)?))
+    }
+
+    /// The position one step in `direction`, `None` past the top or left
+    /// edge. The board decides where the other edges are, see
+    /// [`Board::neighbor`].
+    pub fn step(self, direction: Direction) -> Option<Pos>{
+        Some(match direction{
+            Direction::Up => Pos(self.0, self.1.checked_sub(1)?),
+            Direction::Down => Pos(self.0, self.1.checked_add(1)?),
+            Direction::Left => Pos(self.0.checked_sub(1)?, self.1),
+            Direction::Right => Pos(self.0.checked_add(1)?, self.1),
+        })
+    }
+
+    /// The cells the position is drawn on in `area` with the view scrolled to
+    /// `offset` and tiles `tile_width` columns wide, `None` if it's out of
+    /// view.
+    pub fn to_screen(self, offset: Pos, area: Rect, tile_width: u16) -> Option<Rect>{
+        let x = self.0.checked_sub(offset.0)?;
+        let y = self.1.checked_sub(offset.1)?;
+        (x < (area.width / tile_width) as usize && y < area.height as usize)
+            .then(|| Rect::new(area.x + x as u16 * tile_width, area.y + y as u16, tile_width, 1))
+    }
+
+    /// The position drawn at `position`, the other way around from
+    /// [`Pos::to_screen`]. It still has to be checked against the board.
+    pub fn from_screen(position: Position, offset: Pos, area: Rect, tile_width: u16) -> Option<Pos>{
+        let x = position.x.checked_sub(area.x)? / tile_width;
+        (area.contains(position) && x < area.width / tile_width).then(|| {
+            Pos(x as usize + offset.0, (position.y - area.y) as usize + offset.1)
+        })
+    }
+}
+
+/// Room a board side of `len` tiles needs with a border around it, capped
+/// instead of overflowing for huge boards.
+pub fn frame_size(len: usize) -> u16{
+    u16::try_from(len.saturating_add(2)).unwrap_or(u16::MAX)
+}
 
 impl From<Pos> for Position{
     fn from(value: Pos) -> Self {
@@ -123,10 +965,255 @@ impl Add<Pos> for Pos{
     }
 }
 
-impl Sub<Pos> for Pos{
-    type Output = Pos;
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn get_tile_is_bounds_checked(){
+        let mut board = Board::new(3, 2);
+        board.set_tile(Pos(0, 1), Tile::Wall);
+        assert_eq!(board.get_tile(Pos(0, 1)), Some(&Tile::Wall));
+        assert_eq!(board.get_tile(Pos(3, 0)), None);
+        assert_eq!(board.get_tile(Pos(0, 2)), None);
+        board.set_tile(Pos(3, 0), Tile::Box);
+        assert!(board.rows().flatten().all(|tile| *tile != Tile::Box));
+    }
 
-    fn sub(self, rhs: Pos) -> Self::Output {
-        Pos(self.0 - rhs.0, self.1 - rhs.1)
+    #[test]
+    fn pos_sub_does_not_underflow(){
+        assert_eq!(Pos(3, 4).checked_sub(Pos(1, 1)), Some(Pos(2, 3)));
+        assert_eq!(Pos(1, 1).checked_sub(Pos(3, 0)), None);
+    }
+
+    #[test]
+    fn step_stops_at_the_top_left_edges(){
+        assert_eq!(Pos(0, 0).step(Direction::Up), None);
+        assert_eq!(Pos(0, 0).step(Direction::Left), None);
+        assert_eq!(Pos(0, 0).step(Direction::Down), Some(Pos(0, 1)));
+        assert_eq!(Pos(0, 0).step(Direction::Right), Some(Pos(1, 0)));
+        assert_eq!(Pos(usize::MAX, 0).step(Direction::Right), None);
+    }
+
+    #[test]
+    fn neighbors_stay_on_the_board(){
+        let board = Board::new(3, 2);
+        assert_eq!(board.neighbor(Pos(2, 1), Direction::Right), None);
+        assert_eq!(board.neighbor(Pos(2, 1), Direction::Down), None);
+        let mut corner: Vec<_> = board.neighbors(Pos(0, 0)).collect();
+        corner.sort_by_key(|(_, pos)| (pos.1, pos.0));
+        assert_eq!(corner, [(Direction::Right, Pos(1, 0)), (Direction::Down, Pos(0, 1))]);
+        assert_eq!(board.neighbors(Pos(1, 1)).count(), 3);
+    }
+
+    #[test]
+    fn rows_split_the_tiles_by_width(){
+        let mut board = Board::new(3, 2);
+        board.set_tile(Pos(2, 0), Tile::Wall);
+        let rows: Vec<_> = board.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.len() == 3));
+        assert_eq!(rows[0][2], Tile::Wall);
+        assert_eq!(rows[1][2], Tile::Empty);
+    }
+
+    #[test]
+    fn rows_shorten_runs_of_three_or_more(){
+        let red = Tile::ColoredBox(BoxColor::Red);
+        let row = [
+            Tile::Wall, Tile::Empty, Tile::Empty, Tile::Box, Tile::Box, Tile::Box, Tile::Box,
+            red, red, red, Tile::ColoredBox(BoxColor::Blue), Tile::Wall, Tile::Wall,
+        ];
+        let encoded = encode_row(&row);
+        assert_eq!(encoded, "#--4*3RB##");
+        assert_eq!(decode_row(0, &encoded), Ok(row.to_vec()));
+        assert_eq!(decode_row(0, "#2-1*"), Ok(vec![Tile::Wall, Tile::Empty, Tile::Empty, Tile::Box]));
+    }
+
+    #[test]
+    fn boards_round_trip_through_ron(){
+        let mut board = Board::new(5, 3);
+        board.set_tile(Pos(1, 1), Tile::ColoredBox(BoxColor::Green));
+        board.set_tile(Pos(4, 2), Tile::HeavyBox);
+        board.toggle_target(Pos(2, 1), Some(BoxColor::Green));
+        let saved = ron::to_string(&board).unwrap();
+        assert!(saved.contains("rows"));
+        assert!(ron::from_str::<Board>(&saved).unwrap() == board);
+    }
+
+    #[test]
+    fn legacy_maps_still_load(){
+        let legacy: LegacyBoard = ron::from_str(
+            "(width: 3, tiles: [Wall, Empty, Box, Wall, Empty, Empty], player_pos: (1, 0), goal: (2, 1))"
+        ).unwrap();
+        let board = Board::try_from(legacy).unwrap();
+        assert_eq!(board.players, [Pos(1, 0)]);
+        assert_eq!(board.goals, [Pos(2, 1)]);
+        assert_eq!(board.get_tile(Pos(2, 0)), Some(&Tile::Box));
+        assert_eq!(board.rows().count(), 2);
+    }
+
+    fn load(rows: &[&str], players: &str) -> Result<Board, BoardError>{
+        let rows = rows.iter().map(|row| format!("{:?}", row)).collect::<Vec<_>>().join(", ");
+        let repr: BoardRepr = ron::from_str(&format!("(rows: [{}], players: [{}], goals: [])", rows, players)).unwrap();
+        Board::try_from(repr)
+    }
+
+    #[test]
+    fn bad_maps_say_what_is_wrong(){
+        assert_eq!(Board::try_new(0, 3).err(), Some(BoardError::Empty));
+        assert_eq!(load(&[], "(0, 0)").err(), Some(BoardError::Empty));
+        assert_eq!(load(&["###", "##"], "(0, 0)").err(), Some(BoardError::RowLength{row: 1, len: 2, width: 3}));
+        assert_eq!(load(&["#x#"], "(0, 0)").err(), Some(BoardError::UnknownTile('x')));
+        assert_eq!(load(&["#-#", "#3"], "(0, 0)").err(), Some(BoardError::DanglingCount{row: 1}));
+        assert_eq!(load(&["#-#"], "").err(), Some(BoardError::NoPlayers));
+        assert_eq!(load(&["#-#"], "(1, 1)").err(), Some(BoardError::OutOfBounds{what: "player", pos: Pos(1, 1)}));
+        let legacy: LegacyBoard = ron::from_str("(width: 2, tiles: [Wall, Wall, Wall], players: [(0, 0)])").unwrap();
+        assert_eq!(Board::try_from(legacy).err(), Some(BoardError::Ragged{tiles: 3, width: 2}));
+    }
+
+    #[test]
+    fn huge_counts_are_rejected_without_allocating(){
+        assert_eq!(decode_row(0, "99999999999999999999#"), Err(BoardError::RowTooLong{row: 0}));
+        assert_eq!(load(&["-", "60#60#"], "(0, 0)").err(), Some(BoardError::RowTooLong{row: 1}));
+        assert_eq!(Board::try_new(MAX_SIZE + 1, 1).err(), Some(BoardError::RowTooLong{row: 0}));
+    }
+
+    #[test]
+    fn auto_wall_grows_the_board_to_close_off_the_edges(){
+        let mut board = Board::new(6, 4);
+        board.auto_wall();
+        let rows: Vec<String> = board.rows().map(encode_row).collect();
+        assert_eq!(rows, ["8#", "#6-#", "#6-#", "#6-#", "#6-#", "8#"]);
+        assert_eq!(board.players, [Pos(1, 1)]);
+        assert_eq!(board.goals, [Pos(6, 4)]);
+    }
+
+    #[test]
+    fn auto_wall_only_grows_where_it_has_to(){
+        let mut board = Board::new(5, 4);
+        board.fill_rect(Pos(0, 0), Pos(4, 0), Tile::Wall);
+        board.fill_rect(Pos(0, 0), Pos(0, 3), Tile::Wall);
+        board.set_players(vec![Pos(1, 1)]);
+        board.set_goals(vec![Pos(2, 2)]);
+        board.toggle_target(Pos(3, 2), None);
+        board.auto_wall();
+        let rows: Vec<String> = board.rows().map(encode_row).collect();
+        assert_eq!(rows, ["6#", "#4-#", "#4-#", "#4-#", "6#"]);
+        assert_eq!(board.targets[0].pos, Pos(3, 2));
+    }
+
+    #[test]
+    fn auto_wall_walls_up_edges_that_cannot_grow(){
+        let mut board = Board::new(MAX_SIZE, 3);
+        board.auto_wall();
+        assert_eq!(board.width(), MAX_SIZE);
+        assert_eq!(board.height(), 5);
+        assert_eq!(board.players, [Pos(0, 1)]);
+        assert_eq!(board.get_tile(Pos(0, 1)), Some(&Tile::Empty));
+        assert_eq!(board.get_tile(Pos(0, 2)), Some(&Tile::Wall));
+        assert_eq!(board.get_tile(Pos(MAX_SIZE - 1, 1)), Some(&Tile::Wall));
+    }
+
+    /// The tiles of each row, as written in a saved map without runs.
+    fn tile_rows(board: &Board) -> Vec<String>{
+        board.rows().map(|row| row.iter().map(|&tile| char::from(tile)).collect()).collect()
+    }
+
+    fn first_row(board: &Board) -> String{
+        tile_rows(board).swap_remove(0)
+    }
+
+    #[test]
+    fn chain_boxes_push_the_whole_row(){
+        let mut board = load(&["-&&*-#"], "(0, 0)").unwrap();
+        assert!(board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "--&&*#");
+        assert_eq!(board.players, [Pos(1, 0)]);
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "--&&*#");
+        assert_eq!(board.players, [Pos(1, 0)]);
+    }
+
+    #[test]
+    fn only_players_push_heavy_boxes(){
+        let mut board = load(&["-&%-"], "(0, 0)").unwrap();
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "-&%-");
+        let mut board = load(&["-%--"], "(0, 0)").unwrap();
+        assert!(board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "--%-");
+    }
+
+    #[test]
+    fn plain_boxes_do_not_push_each_other(){
+        let mut board = load(&["-**-"], "(0, 0)").unwrap();
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "-**-");
+    }
+
+    #[test]
+    fn walls_and_players_block_pushes(){
+        let mut board = load(&["-*#"], "(0, 0)").unwrap();
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "-*#");
+        let mut board = load(&["-*--"], "(0, 0), (2, 0)").unwrap();
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "-*--");
+        assert!(board.try_move(1, Direction::Right));
+        assert!(board.try_move(0, Direction::Right));
+        assert_eq!(board.players, [Pos(1, 0), Pos(3, 0)]);
+        assert!(!board.try_move(1, Direction::Left));
+        assert!(!board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "--*-");
+    }
+
+    #[test]
+    fn pulling_drags_the_box_behind(){
+        let mut board = load(&["*---"], "(1, 0)").unwrap();
+        board.set_movement(Movement::Pull);
+        assert!(board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "-*--");
+        assert_eq!(board.players, [Pos(2, 0)]);
+        assert!(!board.try_move(0, Direction::Left));
+        assert!(board.try_move(0, Direction::Right));
+        assert_eq!(first_row(&board), "--*-");
+        assert_eq!(board.players, [Pos(3, 0)]);
+    }
+
+    #[test]
+    fn transforms_move_markers_with_their_tiles(){
+        let mut board = load(&["#*%", "&R-"], "(2, 1)").unwrap();
+        board.set_goals(vec![Pos(1, 1)]);
+        board.toggle_target(Pos(1, 0), None);
+        let expected = [
+            (Transform::Rotate90, ["&#", "R*", "-%"].as_slice()),
+            (Transform::Rotate180, &["-R&", "%*#"]),
+            (Transform::Rotate270, &["%-", "*R", "#&"]),
+            (Transform::FlipHorizontal, &["%*#", "-R&"]),
+            (Transform::FlipVertical, &["&R-", "#*%"]),
+        ];
+        for (transform, rows) in expected{
+            let transformed = board.transformed(transform);
+            assert_eq!(tile_rows(&transformed), rows, "{:?}", transform);
+            assert_eq!(transformed.get_tile(transformed.players[0]), Some(&Tile::Empty), "{:?}", transform);
+            assert_eq!(transformed.get_tile(transformed.goals[0]), Some(&Tile::ColoredBox(BoxColor::Red)), "{:?}", transform);
+            assert_eq!(transformed.get_tile(transformed.targets[0].pos), Some(&Tile::Box), "{:?}", transform);
+        }
+    }
+
+    #[test]
+    fn rotated_and_flipped_levels_normalize_the_same(){
+        let mut board = load(&["#*%-", "&R--"], "(2, 1), (3, 0)").unwrap();
+        board.toggle_target(Pos(3, 1), Some(BoxColor::Red));
+        let normalized = board.normalized();
+        for transform in [Transform::Rotate90, Transform::Rotate180, Transform::Rotate270, Transform::FlipHorizontal, Transform::FlipVertical]{
+            assert!(board.transformed(transform).normalized() == normalized, "{:?}", transform);
+        }
+        let mut players_swapped = board.clone();
+        players_swapped.players.reverse();
+        assert!(players_swapped.normalized() == normalized);
+        board.set_tile(Pos(3, 1), Tile::Wall);
+        assert!(board.normalized() != normalized);
     }
 }
@@ -0,0 +1,17 @@
+use std::cell::RefCell;
+
+use crate::board::Board;
+
+thread_local! {
+    /// Shared by every editor, so regions can be copied from one map to
+    /// another for as long as the game is running.
+    static CLIPBOARD: RefCell<Option<Board>> = const { RefCell::new(None) };
+}
+
+pub fn set(region: Board) {
+    CLIPBOARD.with_borrow_mut(|clipboard| *clipboard = Some(region));
+}
+
+pub fn get() -> Option<Board> {
+    CLIPBOARD.with_borrow(Clone::clone)
+}
@@ -0,0 +1,234 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{Event, KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Paragraph, Widget, WidgetRef},
+};
+use tui_input::{backend::crossterm::to_input_request, Input};
+
+use crate::{
+    board::Board,
+    edit_menu::EditMenu,
+    generator::{self, GeneratorSettings, Progress},
+    menu::{Menu, MenuOptions},
+};
+
+const FIELDS: [&str; 6] = ["Width", "Height", "Boxes", "Walls %", "Difficulty", "Seed"];
+const BLANK: usize = FIELDS.len();
+const GENERATE: usize = FIELDS.len() + 1;
+
+/// Asks for the size of a new map, and either makes an empty one or has the
+/// generator make a level from the settings.
+pub struct CreateMenu {
+    inputs: [Input; FIELDS.len()],
+    focus: usize,
+    pressed: Option<usize>,
+    error: Option<&'static str>,
+    /// The generator running in the background, and where its level comes
+    /// out.
+    generating: Option<(Arc<Progress>, Receiver<Option<Board>>)>,
+    generated: Option<Board>,
+}
+
+impl CreateMenu {
+    pub fn new() -> Self {
+        let defaults = GeneratorSettings::default();
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Self {
+            inputs: [
+                defaults.width.to_string(),
+                defaults.height.to_string(),
+                defaults.boxes.to_string(),
+                defaults.wall_density.to_string(),
+                defaults.difficulty.to_string(),
+                seed.to_string(),
+            ]
+            .map(Input::new),
+            focus: 0,
+            pressed: None,
+            error: None,
+            generating: None,
+            generated: None,
+        }
+    }
+
+    fn field<T: std::str::FromStr>(&self, i: usize) -> Option<T> {
+        self.inputs[i].value().parse().ok()
+    }
+
+    /// Size of a blank map.
+    fn size(&self) -> Option<(usize, usize)> {
+        Some((
+            self.field::<usize>(0)?.clamp(3, 64),
+            self.field::<usize>(1)?.clamp(3, 64),
+        ))
+    }
+
+    /// Settings for the generator, kept to what it can finish.
+    fn settings(&self) -> Option<GeneratorSettings> {
+        Some(GeneratorSettings {
+            width: self.field::<usize>(0)?.clamp(3, generator::MAX_SIDE),
+            height: self.field::<usize>(1)?.clamp(3, generator::MAX_SIDE),
+            boxes: self.field::<usize>(2)?.clamp(1, generator::MAX_BOXES),
+            wall_density: self.field::<u8>(3)?.min(60),
+            difficulty: self.field::<u8>(4)?.clamp(1, generator::MAX_DIFFICULTY),
+            seed: self.field(5)?,
+        })
+    }
+
+    fn create(&mut self, button: usize) -> Option<MenuOptions<()>> {
+        if button == GENERATE {
+            let Some(settings) = self.settings() else {
+                self.error = Some("every field has to be a number");
+                return None;
+            };
+            self.start_generating(settings);
+            return None;
+        }
+        let Some((width, height)) = self.size() else {
+            self.error = Some("every field has to be a number");
+            return None;
+        };
+        Some(MenuOptions::Continue(Box::new(EditMenu::new(Board::new(
+            width, height,
+        )))))
+    }
+
+    fn start_generating(&mut self, settings: GeneratorSettings) {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let shared = Arc::clone(&progress);
+        thread::spawn(move || {
+            let _ = sender.send(generator::generate(&settings, &shared));
+        });
+        self.error = None;
+        self.generating = Some((progress, receiver));
+    }
+}
+
+impl Default for CreateMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Menu for CreateMenu {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        if self.generating.is_some() {
+            return;
+        }
+        match input.code {
+            KeyCode::Down | KeyCode::Tab => self.focus = (self.focus + 1) % (GENERATE + 1),
+            KeyCode::Up | KeyCode::BackTab => self.focus = (self.focus + GENERATE) % (GENERATE + 1),
+            KeyCode::Enter if self.focus >= BLANK => self.pressed = Some(self.focus),
+            KeyCode::Enter => self.focus += 1,
+            KeyCode::Char(x) if !x.is_ascii_digit() => {}
+            _ => {
+                if let (Some(input_field), Some(request)) = (
+                    self.inputs.get_mut(self.focus),
+                    to_input_request(&Event::Key(input)),
+                ) {
+                    input_field.handle(request);
+                }
+            }
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("Up/Down, Tab", "Next or previous field"),
+            ("0-9", "Type a number"),
+            ("Enter", "Next field, or press the button"),
+        ]
+    }
+
+    fn tick(&mut self, _elapsed: Duration) {
+        let Some((_, receiver)) = &self.generating else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(Some(board)) => {
+                self.generating = None;
+                self.generated = Some(board);
+            }
+            Ok(None) | Err(TryRecvError::Disconnected) => {
+                self.generating = None;
+                self.error = Some("couldn't make a level, try another seed or fewer boxes");
+            }
+            Err(TryRecvError::Empty) => {}
+        }
+    }
+
+    /// Stops the generator if it's running instead of leaving.
+    fn back(&mut self) -> bool {
+        match self.generating.take() {
+            Some((progress, _)) => {
+                progress.cancel();
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if let Some(board) = self.generated.take() {
+            return Some(MenuOptions::Continue(Box::new(EditMenu::new(board))));
+        }
+        self.pressed.take().and_then(|button| self.create(button))
+    }
+}
+
+impl WidgetRef for CreateMenu {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let focused = |i: usize| {
+            if i == self.focus {
+                Style::new().reversed()
+            } else {
+                Style::new()
+            }
+        };
+        let mut text = Text::from_iter(FIELDS.iter().zip(&self.inputs).enumerate().map(
+            |(i, (name, input))| {
+                Line::from_iter([
+                    format!("{:>10}: ", name).into(),
+                    Span::styled(format!("{:<12}", input.value()), focused(i)),
+                ])
+            },
+        ));
+        text.push_line("");
+        text.push_line(Line::from_iter([
+            Span::styled("[Blank]", focused(BLANK)),
+            "   ".into(),
+            Span::styled("[Generate]", focused(GENERATE)),
+        ]));
+        let status = match &self.generating {
+            Some((progress, _)) => format!(
+                "Generating, attempt {}/{} (Esc to stop)",
+                progress.attempt(),
+                generator::ATTEMPTS
+            ),
+            None => self.error.unwrap_or_default().to_owned(),
+        };
+        let block = Block::bordered()
+            .title_alignment(ratatui::layout::Alignment::Center)
+            .title_top("Create")
+            .title_bottom(status)
+            .border_type(ratatui::widgets::BorderType::Rounded);
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
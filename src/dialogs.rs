@@ -0,0 +1,596 @@
+use std::{any::Any, ops::RangeInclusive, path::PathBuf};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{Event, KeyCode, KeyEvent},
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Widget, WidgetRef},
+};
+use tui_input::{backend::crossterm::to_input_request, Input};
+
+use crate::{
+    levels,
+    menu::{Menu, MenuOptions, Popup},
+};
+
+type ReplyFn<T> = Box<dyn FnOnce(T) -> Box<dyn Any>>;
+
+/// Turns what a dialog was answered with into the message its parent gets in
+/// [`Menu::receive`]. The parent picks the message type, so it can tell its
+/// dialogs apart by downcasting.
+struct Reply<T>(Option<ReplyFn<T>>);
+
+impl<T> Reply<T> {
+    fn new<R: Any>(reply: impl FnOnce(T) -> R + 'static) -> Self {
+        Self(Some(Box::new(move |value| Box::new(reply(value)))))
+    }
+
+    fn send(&mut self, value: T) -> Option<MenuOptions<()>> {
+        self.0.take().map(|reply| MenuOptions::Return(reply(value)))
+    }
+}
+
+/// Lines of a question or message, with a gap before the keys to answer it.
+fn question_text<'a>(lines: impl IntoIterator<Item = &'a str>, keys: String) -> Text<'a> {
+    let mut text = Text::from_iter(lines.into_iter().map(Line::from));
+    text.push_line("");
+    text.push_line(keys);
+    text.centered()
+}
+
+/// A yes or no question.
+pub struct Confirm {
+    question: String,
+    answer: Option<bool>,
+    reply: Reply<bool>,
+}
+
+impl Confirm {
+    pub fn new<R: Any>(question: String, reply: impl FnOnce(bool) -> R + 'static) -> Self {
+        Self {
+            question,
+            answer: None,
+            reply: Reply::new(reply),
+        }
+    }
+}
+
+impl Menu for Confirm {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'y') => self.answer = Some(true),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'n') => self.answer = Some(false),
+            _ => {}
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Y", "Yes"), ("N", "No")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting("Confirm", &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        let answer = self.answer.take()?;
+        self.reply.send(answer)
+    }
+}
+
+impl Confirm {
+    fn text(&self) -> Text<'_> {
+        question_text(self.question.lines(), "(Y)es   (N)o".to_owned())
+    }
+}
+
+impl WidgetRef for Confirm {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// A question with more answers than yes or no, each picked with its key.
+/// Answers with the key, in lower case.
+pub struct Choice {
+    title: String,
+    question: String,
+    options: &'static [(char, &'static str)],
+    answer: Option<char>,
+    reply: Reply<char>,
+}
+
+impl Choice {
+    pub fn new<R: Any>(
+        title: String,
+        question: String,
+        options: &'static [(char, &'static str)],
+        reply: impl FnOnce(char) -> R + 'static,
+    ) -> Self {
+        Self {
+            title,
+            question,
+            options,
+            answer: None,
+            reply: Reply::new(reply),
+        }
+    }
+
+    fn text(&self) -> Text<'_> {
+        let keys = self
+            .options
+            .iter()
+            .map(
+                |(key, label)| match label.strip_prefix(key.to_ascii_uppercase()) {
+                    Some(rest) => format!("({}){}", key.to_ascii_uppercase(), rest),
+                    None => format!("({}) {}", key.to_ascii_uppercase(), label),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join("   ");
+        question_text(self.question.lines(), keys)
+    }
+}
+
+impl Menu for Choice {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        if let KeyCode::Char(x) = input.code {
+            let x = x.to_ascii_lowercase();
+            if self.options.iter().any(|(key, _)| *key == x) {
+                self.answer = Some(x);
+            }
+        }
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.title, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        let answer = self.answer.take()?;
+        self.reply.send(answer)
+    }
+}
+
+impl WidgetRef for Choice {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// Tells about something that went wrong, closed with any key.
+pub struct Message {
+    title: String,
+    message: String,
+    closed: bool,
+}
+
+impl Message {
+    pub fn new(title: String, message: String) -> Self {
+        Self {
+            title,
+            message,
+            closed: false,
+        }
+    }
+
+    fn text(&self) -> Text<'_> {
+        question_text(self.message.lines(), "(Enter) OK".to_owned())
+    }
+}
+
+impl Menu for Message {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        if let KeyCode::Enter | KeyCode::Char(' ') = input.code {
+            self.closed = true;
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Enter", "Close")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.title, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        std::mem::take(&mut self.closed).then_some(MenuOptions::GoBack)
+    }
+}
+
+impl WidgetRef for Message {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// Asks for a line of text.
+pub struct TextInput {
+    prompt: String,
+    input: Input,
+    entered: bool,
+    reply: Reply<String>,
+}
+
+impl TextInput {
+    pub fn new<R: Any>(
+        prompt: String,
+        initial: String,
+        reply: impl FnOnce(String) -> R + 'static,
+    ) -> Self {
+        Self {
+            prompt,
+            input: Input::new(initial),
+            entered: false,
+            reply: Reply::new(reply),
+        }
+    }
+}
+
+impl Menu for TextInput {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Enter => self.entered = true,
+            _ => {
+                if let Some(request) = to_input_request(&Event::Key(input)) {
+                    self.input.handle(request);
+                }
+            }
+        }
+    }
+
+    fn captures_text(&self) -> bool {
+        true
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Enter", "Done")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.prompt, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if !std::mem::take(&mut self.entered) {
+            return None;
+        }
+        self.reply.send(self.input.value().to_owned())
+    }
+}
+
+impl TextInput {
+    fn text(&self) -> Text<'_> {
+        Text::from(Span::styled(
+            format!("{:<24}", self.input.value()),
+            Style::new().reversed(),
+        ))
+    }
+}
+
+impl WidgetRef for TextInput {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// Asks for a whole number in `range`.
+pub struct NumberInput {
+    prompt: String,
+    range: RangeInclusive<usize>,
+    input: Input,
+    entered: bool,
+    error: bool,
+    reply: Reply<usize>,
+}
+
+impl NumberInput {
+    pub fn new<R: Any>(
+        prompt: String,
+        range: RangeInclusive<usize>,
+        initial: usize,
+        reply: impl FnOnce(usize) -> R + 'static,
+    ) -> Self {
+        Self {
+            prompt,
+            range,
+            input: Input::new(initial.to_string()),
+            entered: false,
+            error: false,
+            reply: Reply::new(reply),
+        }
+    }
+}
+
+impl Menu for NumberInput {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Enter => self.entered = true,
+            KeyCode::Char(x) if !x.is_ascii_digit() => {}
+            _ => {
+                if let Some(request) = to_input_request(&Event::Key(input)) {
+                    self.input.handle(request);
+                }
+            }
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("0-9", "Type the number"), ("Enter", "Done")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.prompt, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if !std::mem::take(&mut self.entered) {
+            return None;
+        }
+        match self.input.value().parse() {
+            Ok(number) if self.range.contains(&number) => self.reply.send(number),
+            _ => {
+                self.error = true;
+                None
+            }
+        }
+    }
+}
+
+impl NumberInput {
+    fn text(&self) -> Text<'_> {
+        let mut text = Text::from(Span::styled(
+            format!("{:<8}", self.input.value()),
+            Style::new().reversed(),
+        ));
+        if self.error {
+            text.push_line(format!(
+                "has to be from {} to {}",
+                self.range.start(),
+                self.range.end()
+            ));
+        }
+        text
+    }
+}
+
+impl WidgetRef for NumberInput {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// How many matches [`Search`] shows at once.
+const SEARCH_ROWS: usize = 10;
+
+/// How well `query` matches `name`, lower is better. Every character of the
+/// query has to appear in the name in order, ignoring case; matches that
+/// start early and don't skip much rank first.
+fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    let mut name = name.chars().map(|c| c.to_ascii_lowercase()).enumerate();
+    let mut score = 0;
+    let mut last = None;
+    for c in query.chars().map(|c| c.to_ascii_lowercase()) {
+        let (i, _) = name.find(|&(_, n)| n == c)?;
+        score += match last {
+            Some(last) => i - last - 1,
+            None => i,
+        };
+        last = Some(i);
+    }
+    Some(score)
+}
+
+/// Picks one of `entries` by typing part of its name. Answers with the
+/// index of the entry, ties keep the order they were given in.
+pub struct Search {
+    title: String,
+    entries: Vec<String>,
+    input: Input,
+    /// Indices of the entries matching the input, best first.
+    matches: Vec<usize>,
+    selected: usize,
+    picked: bool,
+    reply: Reply<usize>,
+}
+
+impl Search {
+    pub fn new<R: Any>(
+        title: String,
+        entries: Vec<String>,
+        reply: impl FnOnce(usize) -> R + 'static,
+    ) -> Self {
+        let mut search = Self {
+            title,
+            entries,
+            input: Input::default(),
+            matches: Vec::new(),
+            selected: 0,
+            picked: false,
+            reply: Reply::new(reply),
+        };
+        search.update_matches();
+        search
+    }
+
+    fn update_matches(&mut self) {
+        let mut matches: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| Some((fuzzy_score(self.input.value(), entry)?, i)))
+            .collect();
+        matches.sort_by_key(|&(score, _)| score);
+        self.matches = matches.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+
+    /// Always the same size, so the popup doesn't jump around while typing.
+    fn text(&self) -> Text<'_> {
+        let width = self.entries.iter().map(String::len).max().unwrap_or(0) + 2;
+        let mut text = Text::from(Span::styled(
+            format!("{:<width$}", self.input.value()),
+            Style::new().reversed(),
+        ));
+        let first = self.selected.saturating_sub(SEARCH_ROWS - 1);
+        for row in first..first + SEARCH_ROWS {
+            text.push_line(match self.matches.get(row) {
+                Some(&i) => format!(
+                    "{}{}",
+                    if row == self.selected { "> " } else { "  " },
+                    self.entries[i]
+                ),
+                None => String::new(),
+            });
+        }
+        text
+    }
+}
+
+impl Menu for Search {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Down if !self.matches.is_empty() => {
+                self.selected = (self.selected + 1) % self.matches.len()
+            }
+            KeyCode::Up if !self.matches.is_empty() => {
+                self.selected = (self.selected + self.matches.len() - 1) % self.matches.len()
+            }
+            KeyCode::Enter => self.picked = true,
+            _ => {
+                if let Some(request) = to_input_request(&Event::Key(input)) {
+                    if self
+                        .input
+                        .handle(request)
+                        .is_some_and(|change| change.value)
+                    {
+                        self.update_matches();
+                    }
+                }
+            }
+        }
+    }
+
+    fn captures_text(&self) -> bool {
+        true
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("Type", "Search"),
+            ("Up/Down", "Pick a match"),
+            ("Enter", "Run it"),
+        ]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.title, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if !std::mem::take(&mut self.picked) {
+            return None;
+        }
+        let index = *self.matches.get(self.selected)?;
+        self.reply.send(index)
+    }
+}
+
+impl WidgetRef for Search {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
+
+/// Picks one of the maps in the data directory.
+pub struct FilePicker {
+    title: String,
+    files: Vec<PathBuf>,
+    selected: usize,
+    picked: bool,
+    reply: Reply<PathBuf>,
+}
+
+impl FilePicker {
+    pub fn new<R: Any>(title: String, reply: impl FnOnce(PathBuf) -> R + 'static) -> Self {
+        Self {
+            title,
+            files: levels::data_dir()
+                .and_then(|dir| levels::list(&dir))
+                .unwrap_or_default(),
+            selected: 0,
+            picked: false,
+            reply: Reply::new(reply),
+        }
+    }
+}
+
+impl Menu for FilePicker {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Down if !self.files.is_empty() => {
+                self.selected = (self.selected + 1) % self.files.len()
+            }
+            KeyCode::Up if !self.files.is_empty() => {
+                self.selected = (self.selected + self.files.len() - 1) % self.files.len()
+            }
+            KeyCode::Enter => self.picked = true,
+            _ => {}
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Up/Down", "Pick a map"), ("Enter", "Done")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting(&self.title, &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if !std::mem::take(&mut self.picked) {
+            return None;
+        }
+        let file = self.files.get(self.selected)?.clone();
+        self.reply.send(file)
+    }
+}
+
+impl FilePicker {
+    fn text(&self) -> Text<'_> {
+        let mut text = Text::from_iter(self.files.iter().enumerate().map(|(i, path)| {
+            Line::from(format!(
+                "{}{:<24}",
+                if i == self.selected { "> " } else { "  " },
+                levels::name(path)
+            ))
+        }));
+        if self.files.is_empty() {
+            text.push_line("No maps yet");
+        }
+        text
+    }
+}
+
+impl WidgetRef for FilePicker {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
@@ -0,0 +1,44 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{board::Board, solver};
+
+/// How far the solver may search when rating a level. Levels it can't solve
+/// in time don't get a rating.
+const MAX_SOLVER_NODES: usize = 500_000;
+/// Largest level, in tiles, that's rated at all. The search on anything
+/// bigger mostly runs into `MAX_SOLVER_NODES` anyway.
+const MAX_TILES: usize = 40 * 40;
+
+/// How hard a level is, measured on its shortest solution.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub moves: usize,
+    pub pushes: usize,
+    pub nodes: usize,
+    pub branching: f32,
+    /// Everything above folded into one number, higher is harder.
+    pub score: u32,
+}
+
+impl Difficulty {
+    /// `None` for levels that are too big to rate, that the solver can't
+    /// solve in time, or when `stop` returns true before it's done.
+    pub fn estimate_until(board: &Board, stop: impl Fn() -> bool) -> Option<Self> {
+        if board.width() * board.height() > MAX_TILES {
+            return None;
+        }
+        let solution = solver::solve_until(board, MAX_SOLVER_NODES, stop)?;
+        let moves = solution.moves.len();
+        let score = moves as f32
+            + 4.0 * solution.pushes as f32
+            + 10.0 * (solution.nodes as f32 + 1.0).log2()
+            + 5.0 * solution.branching;
+        Some(Self {
+            moves,
+            pushes: solution.pushes,
+            nodes: solution.nodes,
+            branching: solution.branching,
+            score: score.round() as u32,
+        })
+    }
+}
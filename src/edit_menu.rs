@@ -1,26 +1,94 @@
-use std::{fs, ops::Add, path::Path};
+use std::{
+    any::Any,
+    cell::Cell,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use ratatui::{
     buffer::Buffer,
-    crossterm::event::{KeyCode, KeyEvent},
-    layout::{Constraint, Layout, Margin, Rect},
-    text::{Text, ToLine},
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::Style,
+    text::Line,
     widgets::{Block, StatefulWidget, Widget, WidgetRef},
 };
-use ron::ser::PrettyConfig;
 
 use crate::{
-    board::{Board, Pos},
-    menu::Menu,
-    tools::{MenuLayer, MenuValue},
+    board::{frame_size, Board, Direction, Movement, Pos, Transform, WinCondition, MAX_SIZE},
+    clipboard,
+    dialogs::{Choice, FilePicker, Message, NumberInput, Search, TextInput},
+    levels::{self, LevelFile, Metadata},
+    menu::{Menu, MenuOptions},
+    play_menu::PlayMenu,
+    recovery::{self, Recovery},
+    theme,
+    tiles::Tile,
+    tools::{AreaTool, MenuLayer, MenuValue, Tool},
 };
 use anyhow::Result;
 
+/// How long a status message stays in the map's title.
+const STATUS_TIME: Duration = Duration::from_secs(3);
+/// How often the map is written to the recovery file while it's edited.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How many of the last used tools the command palette lists first.
+const RECENT_TOOLS: usize = 5;
+
+/// Something the command palette can run.
+enum Command {
+    /// Selects the tool at this path in the tool menu.
+    Tool(Vec<usize>),
+    PlayTest,
+    SetTitle,
+    Resize,
+    Import,
+}
+
+/// Replies from the dialogs the editor opens, see [`EditMenu::receive`].
+enum Reply {
+    Title(String),
+    Width(usize),
+    Resize(usize, usize),
+    Import(PathBuf),
+    /// The key picked when asked whether to save before leaving.
+    Leave(char),
+    Run(Command),
+}
+
 pub struct EditMenu {
     cursor: Pos,
     buffer: Board,
     path: Option<Box<Path>>,
+    metadata: Metadata,
     current_tool: Vec<usize>,
+    /// Tile the fill and drawing tools use, the last one picked.
+    brush: Tile,
+    /// First corner of the area being selected, or the start of the shape
+    /// being drawn.
+    anchor: Option<Pos>,
+    /// A region picked up by the move tool, with where it came from. The
+    /// transform tools work on it instead of the map while it's there.
+    floating: Option<(Pos, Board)>,
+    play_test: bool,
+    /// Top left corner of the part of the map in view.
+    offset: Pos,
+    /// Where the map and the tools were last drawn, for the mouse.
+    map_area: Cell<Rect>,
+    tool_area: Cell<Rect>,
+    dragging: bool,
+    /// A message about the last thing that happened, and how much longer to
+    /// show it.
+    status: Option<(String, Duration)>,
+    since_autosave: Duration,
+    /// The map as it was last loaded or saved, `None` if it never was.
+    saved: Option<LevelFile>,
+    /// Paths of the last used tools, latest first.
+    recent_tools: Vec<Vec<usize>>,
+    /// A dialog to open on top of the editor.
+    dialog: Option<Box<dyn Menu<Output = ()>>>,
+    leave: bool,
 }
 
 impl Default for EditMenu {
@@ -29,50 +97,453 @@ impl Default for EditMenu {
             cursor: Pos(0, 0),
             buffer: Board::new(20, 20),
             path: Default::default(),
+            metadata: Metadata::new(),
             current_tool: vec![0],
+            brush: Tile::Wall,
+            anchor: None,
+            floating: None,
+            play_test: false,
+            offset: Pos(0, 0),
+            map_area: Cell::default(),
+            tool_area: Cell::default(),
+            dragging: false,
+            status: None,
+            since_autosave: Duration::ZERO,
+            saved: None,
+            recent_tools: Vec::new(),
+            dialog: None,
+            leave: false,
         }
     }
 }
 
 impl EditMenu {
+    pub fn new(buffer: Board) -> Self {
+        Self {
+            buffer,
+            ..Default::default()
+        }
+    }
+
     pub fn from_path(path: Box<Path>) -> Result<Self> {
+        let file = levels::load_file(&path)?;
         Ok(Self {
-            buffer: ron::from_str(&fs::read_to_string(&path)?)?,
+            buffer: file.board.clone(),
             path: Some(path),
+            metadata: file.metadata.clone(),
+            saved: Some(file),
             ..Default::default()
         })
     }
 
-    fn save(&self) -> bool {
-        if let Some(path) = &self.path {
-            if let Ok(content) = ron::ser::to_string_pretty(&self.buffer, PrettyConfig::default()) {
-                return fs::write(path, content).is_ok();
+    /// Picks up where a session that didn't end normally left off.
+    pub fn recovered(recovered: Recovery) -> Self {
+        let mut menu = Self {
+            buffer: recovered.file.board,
+            path: recovered.path.map(PathBuf::into_boxed_path),
+            metadata: recovered.file.metadata,
+            ..Default::default()
+        };
+        menu.set_status("Recovered unsaved changes".to_owned());
+        menu
+    }
+
+    /// Hands the map as it is now to the recovery module, in case the game
+    /// doesn't get to close normally.
+    fn record_recovery(&self) {
+        if !self.is_dirty() {
+            return;
+        }
+        recovery::record(Recovery {
+            path: self.path.as_deref().map(Path::to_path_buf),
+            file: LevelFile::new(self.metadata.clone(), self.current_board()),
+        });
+    }
+
+    /// Saves to the map's file, picking a fresh one in the data directory
+    /// for maps that were never saved.
+    fn save(&mut self) -> bool {
+        if self.path.is_none() {
+            self.path = levels::new_map_path().ok().map(Into::into);
+        }
+        let file = LevelFile::new(self.metadata.clone(), self.buffer.clone());
+        let saved = self
+            .path
+            .as_ref()
+            .is_some_and(|path| levels::save(path, &file).is_ok());
+        if saved {
+            self.saved = Some(file);
+            recovery::clear();
+        }
+        saved
+    }
+
+    /// Whether there are changes that saving would keep. Maps that were
+    /// never saved always count.
+    fn is_dirty(&self) -> bool {
+        self.saved.as_ref() != Some(&LevelFile::new(self.metadata.clone(), self.current_board()))
+    }
+
+    /// Leaves the editor, or asks first if that would lose changes.
+    fn try_leave(&mut self) {
+        if self.is_dirty() {
+            self.dialog = Some(Box::new(Choice::new(
+                "Leave editor".to_owned(),
+                format!("{} has unsaved changes.", self.name()),
+                &[('s', "Save"), ('d', "Discard"), ('c', "Cancel")],
+                Reply::Leave,
+            )));
+        } else {
+            self.leave = true;
+        }
+    }
+
+    fn handle_leave_answer(&mut self, answer: char) {
+        match answer {
+            's' => {
+                self.cancel();
+                if self.save() {
+                    self.leave = true;
+                } else {
+                    self.show_error(format!("Couldn't save {}.", self.name()));
+                }
             }
+            'd' => self.leave = true,
+            _ => {}
+        }
+    }
+
+    /// Every tool and editor command by name, the recently used tools
+    /// first.
+    fn commands(&self) -> Vec<(String, Command)> {
+        let tools = MenuLayer::STARTLAYER.terminals();
+        let recent = self
+            .recent_tools
+            .iter()
+            .filter_map(|path| tools.iter().find(|(tool, _)| tool == path));
+        let rest = tools
+            .iter()
+            .filter(|(path, _)| !self.recent_tools.contains(path));
+        recent
+            .chain(rest)
+            .map(|(path, value)| (value.name().to_owned(), Command::Tool(path.clone())))
+            .chain([
+                ("Play-test".to_owned(), Command::PlayTest),
+                ("Set Title".to_owned(), Command::SetTitle),
+                ("Resize Map".to_owned(), Command::Resize),
+                ("Copy From Map".to_owned(), Command::Import),
+            ])
+            .collect()
+    }
+
+    fn open_palette(&mut self) {
+        let (names, mut commands): (Vec<_>, Vec<_>) = self.commands().into_iter().unzip();
+        self.dialog = Some(Box::new(Search::new(
+            "Commands".to_owned(),
+            names,
+            move |i| Reply::Run(commands.swap_remove(i)),
+        )));
+    }
+
+    fn run(&mut self, command: Command) {
+        match command {
+            Command::Tool(path) => {
+                self.select_tool(path);
+                if self.tool().is_some_and(Tool::is_instant) {
+                    self.use_tool();
+                }
+            }
+            Command::PlayTest => self.play_test = true,
+            Command::SetTitle => {
+                self.dialog = Some(Box::new(TextInput::new(
+                    "Title".to_owned(),
+                    self.metadata.title.clone(),
+                    Reply::Title,
+                )))
+            }
+            Command::Resize => {
+                self.dialog = Some(Box::new(NumberInput::new(
+                    "Width".to_owned(),
+                    1..=MAX_SIZE,
+                    self.buffer.width(),
+                    Reply::Width,
+                )))
+            }
+            Command::Import => {
+                self.dialog = Some(Box::new(FilePicker::new(
+                    "Copy from".to_owned(),
+                    Reply::Import,
+                )))
+            }
+        }
+    }
+
+    /// Opens a popup with the error over the editor.
+    fn show_error(&mut self, message: String) {
+        self.dialog = Some(Box::new(Message::new("Error".to_owned(), message)));
+    }
+
+    /// The map as it is right now, with a region being moved put back where
+    /// it came from.
+    fn current_board(&self) -> Board {
+        let mut board = self.buffer.clone();
+        if let Some((origin, region)) = &self.floating {
+            board.paste(*origin, region);
+        }
+        board
+    }
+
+    fn resize(&mut self, width: usize, height: usize) {
+        self.cancel();
+        match self.buffer.resized(width, height) {
+            Ok(board) => {
+                self.buffer = board;
+                self.cursor = Pos(self.cursor.0.min(width - 1), self.cursor.1.min(height - 1));
+                self.offset = Pos(0, 0);
+                self.set_status(format!("Resized to {}x{}", width, height));
+            }
+            Err(err) => self.show_error(format!("Couldn't resize: {}", err)),
+        }
+    }
+
+    /// Puts another map on the clipboard, to be placed with the paste tool.
+    fn import(&mut self, path: &Path) {
+        match levels::load(path) {
+            Ok(board) => {
+                clipboard::set(board.region(Pos(0, 0), Pos(board.width() - 1, board.height() - 1)));
+                self.set_status(format!("Copied {}, paste to place it", levels::name(path)));
+            }
+            Err(err) => self.show_error(format!("Couldn't load {}: {}", levels::name(path), err)),
+        }
+    }
+
+    fn set_status(&mut self, message: String) {
+        self.status = Some((message, STATUS_TIME));
+    }
+
+    fn name(&self) -> &str {
+        self.path.as_deref().map(levels::name).unwrap_or("*Unsaved")
+    }
+
+    fn move_cursor(&mut self, direction: Direction) {
+        if let Some(next) = self.buffer.neighbor(self.cursor, direction) {
+            self.cursor = next;
+        }
+    }
+
+    /// Scrolls just far enough for the cursor to be in view.
+    fn scroll_to_cursor(&mut self) {
+        let area = self.map_area.get();
+        let (width, height) = (
+            (area.width / theme::current().tile_width).max(1) as usize,
+            area.height.max(1) as usize,
+        );
+        self.offset = Pos(
+            self.offset
+                .0
+                .min(self.cursor.0)
+                .max((self.cursor.0 + 1).saturating_sub(width)),
+            self.offset
+                .1
+                .min(self.cursor.1)
+                .max((self.cursor.1 + 1).saturating_sub(height)),
+        );
+    }
+
+    fn scroll(&mut self, x: isize, y: isize) {
+        self.offset = Pos(
+            self.offset
+                .0
+                .saturating_add_signed(x)
+                .min(self.buffer.width() - 1),
+            self.offset
+                .1
+                .saturating_add_signed(y)
+                .min(self.buffer.height() - 1),
+        );
+    }
+
+    /// Whether the tool takes two corners, the first use only marks where it
+    /// starts.
+    fn tool_uses_anchor(&self) -> bool {
+        matches!(self.tool(), Some(Tool::AreaTool(_) | Tool::Shape(_)))
+    }
+
+    fn select_tool(&mut self, path: Vec<usize>) {
+        self.anchor = None;
+        self.current_tool = path;
+        if let Some(MenuValue::Nested(_)) = MenuLayer::STARTLAYER.get_value(&self.current_tool) {
+            self.current_tool.push(0);
+        }
+        self.update_brush();
+    }
+
+    /// Picking a tile in the tool menu makes it the tile the drawing tools
+    /// use, without having to place it first.
+    fn update_brush(&mut self) {
+        if let Some(Tool::Tile(tile)) = self.tool() {
+            self.brush = *tile;
+        }
+    }
+
+    fn tool(&self) -> Option<&'static Tool> {
+        match MenuLayer::STARTLAYER.get_value(&self.current_tool) {
+            Some(MenuValue::Terminal(tool)) => Some(tool),
+            _ => None,
         }
-        false
     }
 
-    pub fn path(&self) -> Option<&Box<Path>> {
-        self.path.as_ref()
+    /// What would be put down by the next use of the tool: a region being
+    /// moved, the clipboard while pasting, or the shape or fill being drawn.
+    fn preview(&self) -> Vec<(Pos, Tile)> {
+        let region = match (&self.floating, self.tool()) {
+            (Some((_, region)), _) => Some(region.clone()),
+            (None, Some(Tool::Paste)) => clipboard::get(),
+            (None, Some(Tool::Shape(shape))) => {
+                return self
+                    .anchor
+                    .map(|anchor| shape.cells(anchor, self.cursor))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|pos| (pos, self.brush))
+                    .collect();
+            }
+            (None, Some(Tool::Bucket)) => {
+                return self
+                    .buffer
+                    .flood_region(self.cursor)
+                    .into_iter()
+                    .map(|pos| (pos, self.brush))
+                    .collect();
+            }
+            _ => None,
+        };
+        let Some(region) = region else {
+            return Vec::new();
+        };
+        (0..region.height())
+            .flat_map(|y| (0..region.width()).map(move |x| Pos(x, y)))
+            .filter_map(|pos| Some((pos + self.cursor, *region.get_tile(pos)?)))
+            .collect()
+    }
+
+    fn use_area_tool(&mut self, area_tool: &AreaTool, anchor: Pos) {
+        let region = self.buffer.region(anchor, self.cursor);
+        match area_tool {
+            AreaTool::Move => {
+                let (top_left, _) = self.buffer.clamp_rect(anchor, self.cursor);
+                self.floating = Some((top_left, region));
+                self.buffer.clear_rect(anchor, self.cursor);
+            }
+            AreaTool::Delete => self.buffer.clear_rect(anchor, self.cursor),
+            AreaTool::Fill => self.buffer.fill_rect(anchor, self.cursor, self.brush),
+            AreaTool::Copy => clipboard::set(region),
+            AreaTool::Cut => {
+                clipboard::set(region);
+                self.buffer.clear_rect(anchor, self.cursor);
+            }
+        }
+    }
+
+    /// Drops the selection, and puts a region being moved back where it was.
+    fn cancel(&mut self) {
+        self.anchor = None;
+        if let Some((origin, region)) = self.floating.take() {
+            self.buffer.paste(origin, &region);
+        }
+    }
+
+    /// Transforms the region being moved, or the whole map if nothing is.
+    fn transform(&mut self, transform: Transform) {
+        match &mut self.floating {
+            Some((_, region)) => *region = region.transformed(transform),
+            None => {
+                self.buffer = self.buffer.transformed(transform);
+                self.cursor = Pos(
+                    self.cursor.0.min(self.buffer.width() - 1),
+                    self.cursor.1.min(self.buffer.height() - 1),
+                );
+            }
+        }
+    }
+
+    fn use_tool(&mut self) {
+        if self.tool().is_some() {
+            self.recent_tools.retain(|path| *path != self.current_tool);
+            self.recent_tools.insert(0, self.current_tool.clone());
+            self.recent_tools.truncate(RECENT_TOOLS);
+        }
+        if let Some(Tool::Transform(transform)) = self.tool() {
+            self.transform(*transform);
+            return;
+        }
+        if let Some((_, region)) = self.floating.take() {
+            self.buffer.paste(self.cursor, &region);
+            return;
+        }
+        if let Some(tool) = self.tool() {
+            match tool {
+                Tool::Tile(tile) => self.buffer.set_tile(self.cursor, *tile),
+                Tool::Player => self.buffer.toggle_player(self.cursor),
+                Tool::Goal => self.buffer.toggle_goal(self.cursor),
+                Tool::Target(color) => self.buffer.toggle_target(self.cursor, *color),
+                Tool::WinCondition => {
+                    self.buffer
+                        .set_win_condition(match self.buffer.win_condition() {
+                            WinCondition::AnyPlayer => WinCondition::AllPlayers,
+                            WinCondition::AllPlayers => WinCondition::AnyPlayer,
+                        })
+                }
+                Tool::Movement => self.buffer.set_movement(self.buffer.movement().reversed()),
+                Tool::AreaTool(area_tool) => match self.anchor.take() {
+                    Some(anchor) => self.use_area_tool(area_tool, anchor),
+                    None => self.anchor = Some(self.cursor),
+                },
+                Tool::Paste => {
+                    if let Some(region) = clipboard::get() {
+                        self.buffer.paste(self.cursor, &region);
+                    }
+                }
+                Tool::Shape(shape) => match self.anchor.take() {
+                    Some(anchor) => shape
+                        .cells(anchor, self.cursor)
+                        .into_iter()
+                        .for_each(|pos| self.buffer.set_tile(pos, self.brush)),
+                    None => self.anchor = Some(self.cursor),
+                },
+                Tool::Bucket => self
+                    .buffer
+                    .flood_region(self.cursor)
+                    .into_iter()
+                    .for_each(|pos| self.buffer.set_tile(pos, self.brush)),
+                Tool::AutoWall => self.buffer.auto_wall(),
+                Tool::Transform(_) => {}
+                Tool::Save => {
+                    if self.save() {
+                        self.set_status(format!("Saved {}", self.name()));
+                    } else {
+                        self.show_error(format!("Couldn't save {}.", self.name()));
+                    }
+                }
+            }
+        }
     }
 }
 
 impl Menu for EditMenu {
     type Output = ();
 
-    fn handle_input(&mut self, input: ratatui::crossterm::event::KeyEvent) {
+    fn handle_input(&mut self, input: KeyEvent) {
         match input.code {
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'w') => {
-                self.cursor.1 = self.cursor.1.saturating_sub(1)
-            }
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'a') => {
-                self.cursor.0 = self.cursor.0.saturating_sub(1)
-            }
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'d') => {
-                self.cursor.0 = self.cursor.0.add(1).min(self.buffer.width() - 1)
-            }
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'s') => {
-                self.cursor.1 = self.cursor.1.add(1).min(self.buffer.height() - 1)
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'w') => self.move_cursor(Direction::Up),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'a') => self.move_cursor(Direction::Left),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'s') => self.move_cursor(Direction::Down),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'d') => self.move_cursor(Direction::Right),
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+                if self.anchor.is_some() =>
+            {
+                self.anchor = None;
+                self.handle_input(input);
             }
             KeyCode::Down => {
                 let mut last_tool = self.current_tool.pop().unwrap();
@@ -83,6 +554,7 @@ impl Menu for EditMenu {
                     last_tool %= layer.sub_menu.len();
                     self.current_tool.push(last_tool);
                 }
+                self.update_brush();
             }
             KeyCode::Up => {
                 let mut last_tool: usize = self.current_tool.pop().unwrap();
@@ -93,6 +565,7 @@ impl Menu for EditMenu {
                     last_tool %= layer.sub_menu.len();
                     self.current_tool.push(last_tool);
                 }
+                self.update_brush();
             }
             KeyCode::Left => {
                 if let Some(MenuValue::Nested(_)) =
@@ -100,25 +573,172 @@ impl Menu for EditMenu {
                 {
                     self.current_tool.push(0);
                 }
+                self.update_brush();
+            }
+            KeyCode::Right if self.current_tool.len() > 1 => {
+                self.current_tool.pop();
             }
-            KeyCode::Right => {
-                if self.current_tool.len() > 1 {
-                    self.current_tool.pop();
+            KeyCode::Char(' ') | KeyCode::Enter => self.use_tool(),
+            KeyCode::Char('p') if input.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.open_palette()
+            }
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'p') => self.run(Command::PlayTest),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'t') => self.run(Command::SetTitle),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'r') => self.run(Command::Resize),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'i') => self.run(Command::Import),
+            _ => {}
+        }
+        self.scroll_to_cursor();
+        self.record_recovery();
+    }
+
+    fn handle_mouse(&mut self, input: MouseEvent) {
+        let position = Position::new(input.column, input.row);
+        let on_map = Pos::from_screen(
+            position,
+            self.offset,
+            self.map_area.get(),
+            theme::current().tile_width,
+        )
+        .filter(|pos| pos.0 < self.buffer.width() && pos.1 < self.buffer.height());
+        match (input.kind, on_map) {
+            (MouseEventKind::Down(MouseButton::Left), Some(pos)) => {
+                self.cursor = pos;
+                self.dragging = false;
+                self.use_tool();
+            }
+            (MouseEventKind::Drag(MouseButton::Left), Some(pos)) if pos != self.cursor => {
+                self.cursor = pos;
+                self.dragging = true;
+                if matches!(self.tool(), Some(Tool::Tile(_))) {
+                    self.use_tool();
+                }
+            }
+            (MouseEventKind::Up(MouseButton::Left), Some(pos)) => {
+                self.cursor = pos;
+                if std::mem::take(&mut self.dragging)
+                    && self.anchor.is_some()
+                    && self.tool_uses_anchor()
+                {
+                    self.use_tool();
                 }
             }
+            (MouseEventKind::Down(MouseButton::Left), None) => {
+                if let MenuValue::Nested(layer) = MenuLayer::STARTLAYER {
+                    if let Some(path) =
+                        layer.entry_at(self.tool_area.get(), &self.current_tool, 0, position)
+                    {
+                        self.select_tool(path);
+                    }
+                }
+            }
+            (MouseEventKind::ScrollDown, _) if input.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.scroll(1, 0)
+            }
+            (MouseEventKind::ScrollUp, _) if input.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.scroll(-1, 0)
+            }
+            (MouseEventKind::ScrollDown, _) => self.scroll(0, 1),
+            (MouseEventKind::ScrollUp, _) => self.scroll(0, -1),
+            (MouseEventKind::ScrollRight, _) => self.scroll(1, 0),
+            (MouseEventKind::ScrollLeft, _) => self.scroll(-1, 0),
             _ => {}
         }
+        if input.kind != MouseEventKind::Moved {
+            self.record_recovery();
+        }
+    }
+
+    fn tick(&mut self, elapsed: Duration) {
+        self.since_autosave += elapsed;
+        if self.since_autosave >= AUTOSAVE_INTERVAL {
+            self.since_autosave = Duration::ZERO;
+            if recovery::flush().is_err() {
+                self.set_status("Couldn't write the recovery file".to_owned());
+            }
+        }
+        if let Some((_, left)) = &mut self.status {
+            *left = left.saturating_sub(elapsed);
+            if left.is_zero() {
+                self.status = None;
+            }
+        }
+    }
+
+    /// Cancels the selection if there is one, the editor itself is left
+    /// through [`EditMenu::try_leave`].
+    fn back(&mut self) -> bool {
+        if self.anchor.is_some() || self.floating.is_some() {
+            self.cancel();
+        } else {
+            self.try_leave();
+        }
+        false
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("WASD", "Move the cursor"),
+            ("Arrows", "Pick a tool"),
+            ("Space, Enter", "Use the tool"),
+            ("P", "Play-test the map"),
+            ("T", "Set the title"),
+            ("R", "Resize the map"),
+            ("I", "Copy another map to paste in"),
+            ("Ctrl+P", "Search tools and commands"),
+            ("Mouse", "Paint, drag shapes, pick tools"),
+            ("Wheel", "Scroll, Shift for sideways"),
+        ]
+    }
+
+    fn unsaved_changes(&self) -> Option<&str> {
+        self.is_dirty().then(|| self.name())
+    }
+
+    fn receive(&mut self, result: Box<dyn Any>) {
+        let Ok(reply) = result.downcast::<Reply>() else {
+            return;
+        };
+        match *reply {
+            Reply::Title(title) => self.metadata.title = title,
+            Reply::Width(width) => {
+                self.dialog = Some(Box::new(NumberInput::new(
+                    "Height".to_owned(),
+                    1..=MAX_SIZE,
+                    self.buffer.height(),
+                    move |height| Reply::Resize(width, height),
+                )))
+            }
+            Reply::Resize(width, height) => self.resize(width, height),
+            Reply::Import(path) => self.import(&path),
+            Reply::Leave(answer) => self.handle_leave_answer(answer),
+            Reply::Run(command) => self.run(command),
+        }
+        self.record_recovery();
     }
 
-    fn is_done(&mut self) -> Option<crate::menu::MenuOptions<Self::Output>> {
-        None
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if let Some(dialog) = self.dialog.take() {
+            return Some(MenuOptions::Continue(dialog));
+        }
+        if std::mem::take(&mut self.leave) {
+            recovery::clear();
+            return Some(MenuOptions::GoBack);
+        }
+        std::mem::take(&mut self.play_test).then(|| {
+            MenuOptions::Continue(Box::new(PlayMenu::new(
+                format!("{} (test)", self.name()),
+                self.current_board(),
+            )))
+        })
     }
 }
 
 impl WidgetRef for EditMenu {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let tile_width = theme::current().tile_width as usize;
         let lay = Layout::horizontal([
-            Constraint::Max(self.buffer.width() as u16 + 2),
+            Constraint::Max(frame_size(self.buffer.width().saturating_mul(tile_width))),
             Constraint::Fill(1),
             Constraint::Length(10),
         ])
@@ -126,36 +746,65 @@ impl WidgetRef for EditMenu {
         {
             //Actual Map
             let buf_area = Layout::vertical([
-                Constraint::Max(self.buffer.height() as u16 + 2),
+                Constraint::Max(frame_size(self.buffer.height())),
                 Constraint::Fill(1),
             ])
             .split(lay[0])[0];
             let b = Block::bordered()
+                .title(self.name())
                 .title(
-                    self.path
-                        .as_ref()
-                        .and_then(|path| path.file_stem())
-                        .and_then(|stem| stem.to_str())
-                        .unwrap_or("*Unsaved"),
+                    Line::from(self.status.as_ref().map_or("", |(message, _)| message))
+                        .right_aligned(),
                 )
-                .title_bottom(format!("{}; {}", self.cursor.0, self.cursor.1));
-            let mut offset = Pos(0, 0);
-            self.buffer.render(b.inner(buf_area), buf, &mut offset);
-            if let Some(cell) = buf.cell_mut(
-                self.cursor - offset
-                    + buf_area
-                        .inner(Margin::new(1, 1))
-                        .positions()
-                        .next()
-                        .unwrap()
-                        .into(),
-            ) {
-                cell.set_bg(ratatui::style::Color::Blue);
+                .title_bottom(format!("{}; {}", self.cursor.0, self.cursor.1))
+                .title_bottom(
+                    Line::from(format!(
+                        "{}, {}",
+                        match self.buffer.movement() {
+                            Movement::Push => "push",
+                            Movement::Pull => "pull",
+                        },
+                        match self.buffer.win_condition() {
+                            WinCondition::AnyPlayer => "any player",
+                            WinCondition::AllPlayers => "all players",
+                        }
+                    ))
+                    .right_aligned(),
+                );
+            let inner = b.inner(buf_area);
+            self.map_area.set(inner);
+            let mut offset = self.offset;
+            self.buffer.render(inner, buf, &mut offset);
+            let theme = theme::current();
+            let screen = |pos: Pos| pos.to_screen(offset, inner, theme.tile_width);
+            if let Some(anchor) = self
+                .anchor
+                .filter(|_| matches!(self.tool(), Some(Tool::AreaTool(_))))
+            {
+                let (top_left, size) = self.buffer.clamp_rect(anchor, self.cursor);
+                for pos in (0..size.1).flat_map(|y| (0..size.0).map(move |x| Pos(x, y))) {
+                    if let Some(tile_area) = screen(pos + top_left) {
+                        buf.set_style(tile_area, Style::new().bg(theme.selection));
+                    }
+                }
+            }
+            for (pos, tile) in self.preview() {
+                if pos.0 >= self.buffer.width() || pos.1 >= self.buffer.height() {
+                    continue;
+                }
+                if let Some(tile_area) = screen(pos) {
+                    theme.tile(&tile).apply(buf, tile_area);
+                    buf.set_style(tile_area, Style::new().bg(theme.preview));
+                }
+            }
+            if let Some(tile_area) = screen(self.cursor) {
+                buf.set_style(tile_area, Style::new().bg(theme.cursor));
             }
             b.render(buf_area, buf);
         }
         {
             //Tool area
+            self.tool_area.set(lay[1]);
             if let MenuValue::Nested(layer) = MenuLayer::STARTLAYER {
                 layer.render(lay[1], buf, &mut (&self.current_tool, 0))
             };
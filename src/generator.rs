@@ -0,0 +1,157 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::{
+    board::{Board, Direction, Movement, Pos},
+    solver,
+    tiles::Tile,
+};
+
+/// How many boards the solver may look at before a generated level is thrown
+/// away as too hard to verify.
+const MAX_SOLVER_NODES: usize = 200_000;
+/// How many boards the solver may look at over all attempts together, so a
+/// hopeless set of settings gives up in reasonable time.
+const MAX_TOTAL_NODES: usize = 500_000;
+pub const ATTEMPTS: usize = 32;
+/// Largest levels the generator is asked for, bigger ones rarely get through
+/// the solver.
+pub const MAX_SIDE: usize = 16;
+pub const MAX_BOXES: usize = 4;
+pub const MAX_DIFFICULTY: u8 = 10;
+
+/// Shared with the thread running [`generate`], to follow it and stop it.
+#[derive(Default)]
+pub struct Progress {
+    attempt: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    /// The attempt being made, counting from one.
+    pub fn attempt(&self) -> usize {
+        self.attempt.load(Ordering::Relaxed)
+    }
+
+    /// Makes [`generate`] give up as soon as it notices.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GeneratorSettings {
+    pub width: usize,
+    pub height: usize,
+    pub boxes: usize,
+    /// Chance in percent for each inner cell to become a wall.
+    pub wall_density: u8,
+    /// Scales how long the boxes get dragged away from their targets.
+    pub difficulty: u8,
+    pub seed: u64,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            width: 9,
+            height: 9,
+            boxes: 3,
+            wall_density: 15,
+            difficulty: 3,
+            seed: 0,
+        }
+    }
+}
+
+/// SplitMix64, small and good enough to make levels reproducible from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Makes a solvable level. Boxes start on their targets and the player pulls
+/// them around for a while, the position they end up in is the level. The
+/// result is checked with the solver before it's handed out, and thrown away
+/// if it's solved too quickly for the difficulty. The same settings always
+/// give the same level, `None` if no attempt worked out within the budget or
+/// it was cancelled through `progress`.
+pub fn generate(settings: &GeneratorSettings, progress: &Progress) -> Option<Board> {
+    let mut rng = Rng(settings.seed);
+    let spent = Cell::new(0);
+    let stop = || {
+        spent.set(spent.get() + 1);
+        spent.get() > MAX_TOTAL_NODES || progress.is_cancelled()
+    };
+    (1..=ATTEMPTS)
+        .take_while(|_| spent.get() <= MAX_TOTAL_NODES && !progress.is_cancelled())
+        .find_map(|i| {
+            progress.attempt.store(i, Ordering::Relaxed);
+            attempt(settings, &mut rng, stop)
+        })
+}
+
+fn attempt(settings: &GeneratorSettings, rng: &mut Rng, stop: impl Fn() -> bool) -> Option<Board> {
+    let (width, height) = (settings.width, settings.height);
+    if width < 3 || height < 3 {
+        return None;
+    }
+    let mut board = Board::new(width, height);
+    let mut floor = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if border || rng.below(100) < settings.wall_density as usize {
+                board.set_tile(Pos(x, y), Tile::Wall);
+            } else {
+                floor.push(Pos(x, y));
+            }
+        }
+    }
+    if floor.len() < settings.boxes + 1 {
+        return None;
+    }
+    let mut take = |rng: &mut Rng| floor.swap_remove(rng.below(floor.len()));
+    for _ in 0..settings.boxes {
+        let target = take(rng);
+        board.set_tile(target, Tile::Box);
+        board.toggle_target(target, None);
+    }
+    let goal = take(rng);
+    board.set_players(vec![goal]);
+    board.set_goals(vec![goal]);
+
+    board.set_movement(Movement::Pull);
+    let steps = 20 * settings.difficulty.max(1) as usize * settings.boxes.max(1);
+    for _ in 0..steps {
+        board.try_move(0, Direction::ALL[rng.below(4)]);
+    }
+    board.set_movement(Movement::Push);
+
+    if board
+        .targets()
+        .iter()
+        .all(|target| board.is_target_filled(target))
+    {
+        return None;
+    }
+    let solution = solver::solve_until(&board, MAX_SOLVER_NODES, stop)?;
+    (solution.moves.len() >= 4 * settings.difficulty as usize).then_some(board)
+}
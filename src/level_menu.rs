@@ -0,0 +1,259 @@
+use std::{
+    any::Any,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::TryRecvError,
+    time::Duration,
+};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    text::{Line, Text},
+    widgets::{Block, Paragraph, Widget, WidgetRef},
+};
+
+use crate::{
+    dialogs::Confirm,
+    edit_menu::EditMenu,
+    levels,
+    menu::{Menu, MenuOptions},
+    pack::{Manifest, ManifestEntry, Refresh},
+    play_menu::PlayMenu,
+};
+
+#[derive(Clone, Copy)]
+pub enum LevelAction {
+    Play,
+    Edit,
+}
+
+/// Reply from the [`Confirm`] asked before deleting a map.
+struct Delete(Option<PathBuf>);
+
+/// Lists the maps in the data directory, easiest first, and opens the picked
+/// one.
+pub struct LevelMenu {
+    action: LevelAction,
+    levels: Vec<(PathBuf, ManifestEntry)>,
+    selected: usize,
+    chosen: bool,
+    reverse: bool,
+    error: Option<String>,
+    dialog: Option<Confirm>,
+    /// The data directory and the manifest being brought up to date in the
+    /// background, the new levels show "?" until it's done. Dropping it, with
+    /// the menu or on a reload, stops the rating.
+    rating: Option<(PathBuf, Refresh)>,
+}
+
+/// The levels of a manifest with their paths.
+fn entries(dir: &Path, manifest: Manifest) -> Vec<(PathBuf, ManifestEntry)> {
+    manifest
+        .levels
+        .into_iter()
+        .map(|entry| (entry.path(dir), entry))
+        .collect()
+}
+
+impl LevelMenu {
+    pub fn new(action: LevelAction) -> Self {
+        let mut menu = Self {
+            action,
+            levels: Vec::new(),
+            selected: 0,
+            chosen: false,
+            reverse: false,
+            error: None,
+            dialog: None,
+            rating: None,
+        };
+        menu.reload();
+        menu
+    }
+
+    /// Lists the levels as the manifest last knew them, and starts rating
+    /// the new and changed ones.
+    fn reload(&mut self) {
+        let listed = levels::data_dir().and_then(|dir| {
+            let levels = entries(&dir, Manifest::cached(&dir)?);
+            self.rating = Some((dir.clone(), Manifest::refresh_in_background(dir)));
+            Ok(levels)
+        });
+        match listed {
+            Ok(levels) => self.set_levels(levels),
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Replaces the list, keeping the same level selected if it's still
+    /// there.
+    fn set_levels(&mut self, levels: Vec<(PathBuf, ManifestEntry)>) {
+        let selected = self.levels.get(self.selected).map(|(path, _)| path.clone());
+        self.levels = levels;
+        self.selected = selected
+            .and_then(|selected| self.levels.iter().position(|(path, _)| *path == selected))
+            .unwrap_or(self.selected)
+            .min(self.levels.len().saturating_sub(1));
+    }
+
+    fn ask_delete(&mut self) {
+        let Some((path, _)) = self.levels.get(self.selected) else {
+            return;
+        };
+        let path = path.clone();
+        self.dialog = Some(Confirm::new(
+            format!("Delete {}?", levels::name(&path)),
+            move |yes| Delete(yes.then_some(path)),
+        ));
+    }
+
+    fn open(&mut self) -> Option<MenuOptions<()>> {
+        let (path, _) = self.levels.get(self.selected)?;
+        let menu: anyhow::Result<Box<dyn Menu<Output = ()>>> = match self.action {
+            LevelAction::Play if self.reverse => levels::load(path).and_then(|board| {
+                PlayMenu::reversed(levels::name(path).to_owned(), board)
+                    .map(|menu| Box::new(menu) as _)
+                    .ok_or_else(|| anyhow::anyhow!("the boxes don't fit the targets"))
+            }),
+            LevelAction::Play => PlayMenu::from_path(path).map(|menu| Box::new(menu) as _),
+            LevelAction::Edit => {
+                EditMenu::from_path(path.clone().into_boxed_path()).map(|menu| Box::new(menu) as _)
+            }
+        };
+        match menu {
+            Ok(menu) => Some(MenuOptions::Continue(menu)),
+            Err(err) => {
+                self.error = Some(format!("{}: {}", levels::name(path), err));
+                None
+            }
+        }
+    }
+}
+
+impl Menu for LevelMenu {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Down if !self.levels.is_empty() => {
+                self.selected = (self.selected + 1) % self.levels.len()
+            }
+            KeyCode::Up if !self.levels.is_empty() => {
+                self.selected = (self.selected + self.levels.len() - 1) % self.levels.len()
+            }
+            KeyCode::Enter => {
+                self.reverse = false;
+                self.chosen = true
+            }
+            KeyCode::Char(x)
+                if x.eq_ignore_ascii_case(&'r') && matches!(self.action, LevelAction::Play) =>
+            {
+                self.reverse = true;
+                self.chosen = true
+            }
+            KeyCode::Delete => self.ask_delete(),
+            _ => {}
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        match self.action {
+            LevelAction::Play => &[
+                ("Up/Down", "Pick a level"),
+                ("Enter", "Play it"),
+                ("R", "Play it in reverse"),
+                ("Delete", "Delete it"),
+            ],
+            LevelAction::Edit => &[
+                ("Up/Down", "Pick a level"),
+                ("Enter", "Edit it"),
+                ("Delete", "Delete it"),
+            ],
+        }
+    }
+
+    fn receive(&mut self, result: Box<dyn Any>) {
+        let Ok(delete) = result.downcast::<Delete>() else {
+            return;
+        };
+        let Delete(Some(path)) = *delete else {
+            return;
+        };
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                self.error = None;
+                self.reload();
+            }
+            Err(err) => self.error = Some(format!("{}: {}", levels::name(&path), err)),
+        }
+    }
+
+    fn tick(&mut self, _elapsed: Duration) {
+        let Some((dir, refresh)) = &self.rating else {
+            return;
+        };
+        match refresh.try_recv() {
+            Ok(Ok(manifest)) => {
+                let levels = entries(dir, manifest);
+                self.set_levels(levels);
+                self.rating = None;
+            }
+            Ok(Err(err)) => {
+                self.error = Some(err.to_string());
+                self.rating = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.rating = None,
+        }
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        if let Some(dialog) = self.dialog.take() {
+            return Some(MenuOptions::Continue(Box::new(dialog)));
+        }
+        if std::mem::take(&mut self.chosen) {
+            return self.open();
+        }
+        None
+    }
+}
+
+impl WidgetRef for LevelMenu {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let mut text = Text::from_iter(self.levels.iter().enumerate().map(|(i, (path, entry))| {
+            Line::from_iter([
+                if i == self.selected { "> " } else { "  " }.to_owned(),
+                format!("{:<24}", levels::name(path)),
+                match entry.difficulty {
+                    Some(difficulty) => format!(
+                        "{:>5}  {} moves, {} pushes",
+                        difficulty.score, difficulty.moves, difficulty.pushes
+                    ),
+                    None => format!("{:>5}", "?"),
+                },
+                match &entry.duplicate_of {
+                    Some(original) => format!("  (same as {})", original),
+                    None => String::new(),
+                },
+            ])
+        }));
+        if self.levels.is_empty() {
+            text.push_line("No maps yet, make one with (C)reate");
+        }
+        let block = Block::bordered()
+            .title_alignment(ratatui::layout::Alignment::Center)
+            .title_top(match self.action {
+                LevelAction::Play => "Play ((R)everse)",
+                LevelAction::Edit => "Edit",
+            })
+            .title_bottom(match (&self.error, &self.rating) {
+                (Some(error), _) => error.as_str(),
+                (None, Some(_)) => "Rating levels...",
+                (None, None) => "",
+            })
+            .border_type(ratatui::widgets::BorderType::Rounded);
+        Paragraph::new(text).block(block).render(area, buf);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use directories::ProjectDirs;
+use ron::ser::PrettyConfig;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    board::{Board, LegacyBoard},
+    pack, theme,
+};
+
+/// Folder in the data directory the maps are kept in.
+const LEVELS: &str = "levels";
+
+/// Version of the level files written now. Anything older is migrated when
+/// it's loaded, see [`Stored`].
+pub const VERSION: u32 = 2;
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    /// Seconds since the Unix epoch, `None` for levels from before this was
+    /// recorded.
+    pub created: Option<u64>,
+}
+
+impl Metadata {
+    /// Metadata for a level made right now.
+    pub fn new() -> Self {
+        Self {
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|time| time.as_secs()),
+            ..Default::default()
+        }
+    }
+}
+
+/// What a level file holds.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelFile {
+    pub version: u32,
+    #[serde(default)]
+    pub metadata: Metadata,
+    pub board: Board,
+}
+
+impl LevelFile {
+    pub fn new(metadata: Metadata, board: Board) -> Self {
+        Self {
+            version: VERSION,
+            metadata,
+            board,
+        }
+    }
+}
+
+/// Just the version of a level file. Files from before it was written don't
+/// have one and count as version 0.
+#[derive(Deserialize)]
+struct Version {
+    #[serde(default)]
+    version: u32,
+}
+
+/// A version 1 level file, the board still has a flat list of tiles.
+#[derive(Deserialize)]
+struct LegacyFile {
+    #[serde(default)]
+    metadata: Metadata,
+    board: LegacyBoard,
+}
+
+/// Every format levels were ever saved in.
+enum Stored {
+    /// The board on its own.
+    V0(LegacyBoard),
+    V1(LegacyFile),
+    /// Tiles saved as run-length encoded rows.
+    V2(LevelFile),
+}
+
+impl Stored {
+    fn parse(content: &str) -> Result<Self> {
+        let Version { version } = ron::from_str(content)?;
+        Ok(match version {
+            0 => Stored::V0(ron::from_str(content)?),
+            1 => Stored::V1(ron::from_str(content)?),
+            2 => Stored::V2(ron::from_str(content)?),
+            _ => bail!("saved by a newer version of the game (format {})", version),
+        })
+    }
+
+    /// One version closer to the current format.
+    fn migrate(self) -> Result<Self> {
+        Ok(match self {
+            Stored::V0(board) => Stored::V1(LegacyFile {
+                metadata: Metadata::default(),
+                board,
+            }),
+            Stored::V1(file) => Stored::V2(LevelFile {
+                version: 2,
+                metadata: file.metadata,
+                board: file.board.try_into()?,
+            }),
+            current @ Stored::V2(_) => current,
+        })
+    }
+}
+
+/// One of the game's directories, picked by `which` from the platform's
+/// conventions, created if it doesn't exist yet.
+pub fn game_dir(which: fn(&ProjectDirs) -> &Path) -> Result<PathBuf> {
+    let project_dirs =
+        ProjectDirs::from("", "", "Generic's Sokoban").context("cannot find home directory")?;
+    let path = which(&project_dirs);
+    fs::create_dir_all(path).with_context(|| format!("cannot create {}", path.display()))?;
+    Ok(path.to_path_buf())
+}
+
+/// Directory the maps are stored in, a folder of its own in the game's data
+/// directory. On macOS the data directory is also the config directory, so
+/// the theme would show up as a map otherwise.
+pub fn data_dir() -> Result<PathBuf> {
+    let parent = game_dir(ProjectDirs::data_dir)?;
+    let path = parent.join(LEVELS);
+    if !path.exists() {
+        fs::create_dir_all(&path).with_context(|| format!("cannot create {}", path.display()))?;
+        move_old_maps(&parent, &path)?;
+    }
+    Ok(path)
+}
+
+/// Moves the maps and the manifest saved straight in the data directory,
+/// before maps had a folder of their own, into `to`.
+fn move_old_maps(from: &Path, to: &Path) -> Result<()> {
+    for path in list(from)?
+        .into_iter()
+        .filter(|path| !path.ends_with(theme::CONFIG))
+        .chain(Some(from.join(pack::MANIFEST)).filter(|path| path.exists()))
+    {
+        if let Some(name) = path.file_name() {
+            fs::rename(&path, to.join(name))
+                .with_context(|| format!("cannot move {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Every map in `dir`, sorted by file name.
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut levels: Vec<PathBuf> = fs::read_dir(dir)
+        .context("cannot access game directory")?
+        .filter_map(|file| file.ok().map(|file| file.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .filter(|path| !path.ends_with(pack::MANIFEST))
+        .collect();
+    levels.sort();
+    Ok(levels)
+}
+
+/// A `new_map_N.ron` path in the data directory that isn't taken yet.
+pub fn new_map_path() -> Result<PathBuf> {
+    let path = data_dir()?;
+    let mut name = 1;
+    while path.join(format!("new_map_{}.ron", name)).exists() {
+        name += 1;
+    }
+    Ok(path.join(format!("new_map_{}.ron", name)))
+}
+
+/// Reads a level file of any version, migrating it to the current one.
+pub fn load_file(path: &Path) -> Result<LevelFile> {
+    let mut stored = Stored::parse(&fs::read_to_string(path)?)?;
+    loop {
+        match stored {
+            Stored::V2(file) => return Ok(file),
+            older => stored = older.migrate()?,
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Board> {
+    Ok(load_file(path)?.board)
+}
+
+pub fn save(path: &Path, file: &LevelFile) -> Result<()> {
+    fs::write(
+        path,
+        ron::ser::to_string_pretty(file, PrettyConfig::default())?,
+    )?;
+    Ok(())
+}
+
+pub fn name(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("*Unsaved")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Pos, tiles::Tile};
+
+    /// Writes `content` to a file of its own in the temp directory and loads
+    /// it.
+    fn load_content(name: &str, content: &str) -> Result<LevelFile> {
+        let path =
+            std::env::temp_dir().join(format!("sokoban-{}-{}.ron", std::process::id(), name));
+        fs::write(&path, content)?;
+        let file = load_file(&path);
+        fs::remove_file(&path)?;
+        file
+    }
+
+    #[test]
+    fn unversioned_maps_migrate_to_the_current_version() {
+        let file = load_content(
+            "v0",
+            "(width: 3, tiles: [Wall, Empty, Box, Wall, Empty, Empty], player_pos: (1, 0), goal: (2, 1))",
+        )
+        .unwrap();
+        assert_eq!(file.version, VERSION);
+        assert!(file.metadata == Metadata::default());
+        assert_eq!(file.board.players(), [Pos(1, 0)]);
+        assert_eq!(file.board.goals(), [Pos(2, 1)]);
+        assert_eq!(file.board.get_tile(Pos(2, 0)), Some(&Tile::Box));
+    }
+
+    #[test]
+    fn version_1_files_keep_their_metadata() {
+        let file = load_content(
+            "v1",
+            r#"(version: 1, metadata: (title: "Old", author: "Someone"), board: (width: 2, tiles: [Empty, Wall], players: [(0, 0)], goals: [(0, 0)]))"#,
+        )
+        .unwrap();
+        assert_eq!(file.version, VERSION);
+        assert_eq!(file.metadata.title, "Old");
+        assert_eq!(file.metadata.author, "Someone");
+        assert_eq!(file.board.get_tile(Pos(1, 0)), Some(&Tile::Wall));
+    }
+
+    #[test]
+    fn saved_files_load_back_unchanged() {
+        let mut board = Board::new(4, 3);
+        board.set_tile(Pos(1, 1), Tile::HeavyBox);
+        let file = LevelFile::new(Metadata::new(), board);
+        let path = std::env::temp_dir().join(format!("sokoban-{}-saved.ron", std::process::id()));
+        save(&path, &file).unwrap();
+        let loaded = load_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.unwrap() == file);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let error = load_content("v99", "(version: 99, board: (rows: []))")
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("newer version"));
+    }
+}
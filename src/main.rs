@@ -1,18 +1,39 @@
 use std::io;
+use ratatui::crossterm::{event::{DisableMouseCapture, EnableMouseCapture}, execute};
 mod main_menu;
 mod quit_menu;
 mod board;
+mod clipboard;
 mod tiles;
 mod tools;
 mod edit_menu;
+mod create_menu;
+mod dialogs;
+mod difficulty;
+mod generator;
+mod level_menu;
+mod levels;
+mod pack;
+mod play_menu;
+mod solver;
+mod recovery;
+mod recovery_menu;
+mod theme;
 mod menu;
 use app::App;
 mod app;
 
 fn main() -> io::Result<()> {
+    theme::load().map_err(io::Error::other)?;
     let mut terminal = ratatui::init();
+    recovery::install_panic_hook();
+    execute!(io::stdout(), EnableMouseCapture)?;
     terminal.clear()?;
-    App::new().run(&mut terminal)?;
+    let result = App::new().run(&mut terminal);
+    execute!(io::stdout(), DisableMouseCapture)?;
     ratatui::restore();
-    Ok(())
+    if result.is_ok() {
+        recovery::clear();
+    }
+    result
 }
@@ -1,7 +1,7 @@
-use std::{ffi::OsString, fs};
-
 use crate::{
-    board::Board, edit_menu::EditMenu, menu::{Menu, MenuOptions}
+    create_menu::CreateMenu,
+    level_menu::{LevelAction, LevelMenu},
+    menu::{Menu, MenuOptions},
 };
 use ratatui::{
     buffer::Buffer,
@@ -10,7 +10,6 @@ use ratatui::{
     text::{Line, Text},
     widgets::{Block, Padding, Paragraph, Widget, WidgetRef},
 };
-use ron::ser::PrettyConfig;
 
 pub struct MainMenu {
     selected_tool: Option<MainMenuState>,
@@ -53,41 +52,25 @@ impl Menu for MainMenu {
         }
     }
 
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("P", "Play a level"),
+            ("E", "Edit a level"),
+            ("C", "Create a level"),
+            ("Q", "Quit"),
+        ]
+    }
+
     fn is_done(&mut self) -> std::option::Option<MenuOptions<()>> {
         self.selected_tool.take().map(|tool| match tool {
-            MainMenuState::Play => todo!(),
-            MainMenuState::Edit => todo!(),
-            MainMenuState::Create => {
-                //let board = Board::new(21, 21);
-                //let mut name = 1;
-                //let project_dirs = directories::ProjectDirs::from("", "", "Generic's Sokoban")
-                //    .expect("cannot find home directory");
-                //let path = project_dirs.data_dir();
-                //if fs::read_dir(path).is_err() {
-                //    fs::create_dir_all(path).expect("cannot create game directory");
-                //}
-                //let files: Vec<OsString> = fs::read_dir(path)
-                //    .expect("cannot access game directory")
-                //    .filter_map(|file| file.ok().map(|file| file.file_name()))
-                //    .collect();
-                //while files.iter().any(|file_name| {
-                //    *file_name == Into::<OsString>::into(format!("new_map_{}.ron", name))
-                //}) {
-                //    name += 1;
-                //}
-                //let mut path_buf = path.to_path_buf();
-                //path_buf.push(format!("new_map_{}.ron", name));
-                //fs::write(
-                //    &path_buf,
-                //    ron::ser::to_string_pretty(&board, PrettyConfig::new())
-                //        .expect("couldn't serialize default map"),
-                //)
-                //.expect("couldn't create new map");
-                //
-                //MenuOptions::Continue(Box::new(EditMenu::from_path(path_buf.as_path()).expect("couldn't access new map")))
-                MenuOptions::Continue(Box::new(EditMenu::default()))
+            MainMenuState::Play => {
+                MenuOptions::Continue(Box::new(LevelMenu::new(LevelAction::Play)))
+            }
+            MainMenuState::Edit => {
+                MenuOptions::Continue(Box::new(LevelMenu::new(LevelAction::Edit)))
             }
-            MainMenuState::Quit => MenuOptions::Exit(()),
+            MainMenuState::Create => MenuOptions::Continue(Box::new(CreateMenu::new())),
+            MainMenuState::Quit => MenuOptions::Quit,
         })
     }
 }
@@ -109,10 +92,10 @@ impl WidgetRef for MainMenu {
             .title_alignment(ratatui::layout::Alignment::Center)
             .title_top("Generic's Sokoban")
             .padding(Padding::new(
-                0,                                        // left
-                0,                                        // right
-                (area.height - text.height() as u16) / 2, // top
-                0,                                        // bottom
+                0,                                                    // left
+                0,                                                    // right
+                area.height.saturating_sub(text.height() as u16) / 2, // top
+                0,                                                    // bottom
             ))
             .border_type(ratatui::widgets::BorderType::Rounded);
         Paragraph::new(text)
@@ -1,16 +1,76 @@
-use ratatui::{crossterm::event::KeyEvent, widgets::WidgetRef};
+use std::{any::Any, time::Duration};
+
+use ratatui::{crossterm::event::{KeyEvent, MouseEvent}, text::Text, widgets::WidgetRef};
 
 pub trait Menu: WidgetRef{
     type Output;
 
     fn handle_input(&mut self, input: KeyEvent);
+    /// Menus that can be used with the mouse override this, the rest ignore
+    /// it.
+    fn handle_mouse(&mut self, _input: MouseEvent) {}
+    /// Called every frame on the menu on top with the time since the last
+    /// call, for anything that moves on its own.
+    fn tick(&mut self, _elapsed: Duration) {}
+    /// Called on Esc, the menu is closed if this returns true. Menus with a
+    /// selection or a question open close that first instead.
+    fn back(&mut self) -> bool {
+        true
+    }
+    /// Menus being typed into return true, so printable keys like `?` are
+    /// handed to them instead of doing what they do everywhere else.
+    fn captures_text(&self) -> bool {
+        false
+    }
+    /// The menu's own keys and what they do, for the help overlay.
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+    /// Name of whatever would be lost if the game quit now, checked on every
+    /// menu on the stack before quitting.
+    fn unsaved_changes(&self) -> Option<&str> {
+        None
+    }
+    /// Menus that return this are drawn in a box over the menu under them
+    /// instead of taking the whole screen, and are only given the inside of
+    /// the box to draw in.
+    fn popup(&self) -> Option<Popup<'_>> {
+        None
+    }
+    /// Gets what a menu opened from this one finished with, see
+    /// [`MenuOptions::Return`].
+    fn receive(&mut self, _result: Box<dyn Any>) {}
     fn is_done(&mut self) -> Option<MenuOptions<Self::Output>>;
 }
 
+/// Title and inner size of the box a popup menu is drawn in.
+pub struct Popup<'a>{
+    pub title: &'a str,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl<'a> Popup<'a>{
+    /// A box just big enough for `text` and the title.
+    pub fn fitting(title: &'a str, text: &Text) -> Self{
+        Self{
+            title,
+            width: (text.width() as u16).max(title.len() as u16),
+            height: text.height() as u16,
+        }
+    }
+}
+
 pub enum MenuOptions<T>{
     GoBack,
+    #[allow(dead_code)]
     BackToFirst,
     Continue(Box<dyn Menu<Output = T>>),
+    /// Closes the menu and hands the value to the one under it through
+    /// [`Menu::receive`].
+    Return(Box<dyn Any>),
+    /// Asks whether to quit, mentioning any unsaved work on the stack.
+    Quit,
     Exit(T),
 }
 
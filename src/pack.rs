@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::UNIX_EPOCH,
+};
+
+use anyhow::Result;
+use ron::ser::PrettyConfig;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{board::Board, difficulty::Difficulty, levels};
+
+/// File in a pack directory that remembers what's known about its levels.
+pub const MANIFEST: &str = "manifest.ron";
+
+/// Held while a manifest is written, a stopped refresh can still be saving
+/// while the next one finishes.
+static WRITING: Mutex<()> = Mutex::new(());
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub levels: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: String,
+    /// Modification time of the file when it was rated, so edited levels get
+    /// rated again.
+    pub modified: u64,
+    /// `None` if the solver couldn't solve the level.
+    pub difficulty: Option<Difficulty>,
+    /// An earlier level in the pack that is this one rotated or flipped.
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+}
+
+impl ManifestEntry {
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(&self.file)
+    }
+}
+
+fn modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_secs())
+}
+
+/// A [`Manifest`] being refreshed on its own thread, since rating can take a
+/// while. Dropping it stops the rating, the levels rated so far are still
+/// saved.
+pub struct Refresh {
+    receiver: Receiver<Result<Manifest>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Refresh {
+    /// The refreshed manifest once it's done.
+    pub fn try_recv(&self) -> Result<Result<Manifest>, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+impl Drop for Refresh {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Manifest {
+    fn read(dir: &Path) -> Self {
+        fs::read_to_string(dir.join(MANIFEST))
+            .ok()
+            .and_then(|content| ron::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// The levels in `dir`, reusing the saved entries of those that haven't
+    /// changed and rating the rest with `rate`. Levels `rate` gives up on
+    /// are left out.
+    fn list(dir: &Path, rate: impl Fn(&Path) -> Option<Option<Difficulty>>) -> Result<Self> {
+        let old = Self::read(dir);
+        let mut manifest = Manifest {
+            levels: levels::list(dir)?
+                .into_iter()
+                .filter_map(|path| {
+                    let file = path.file_name()?.to_str()?.to_owned();
+                    let modified = modified(&path);
+                    Some(
+                        match old
+                            .levels
+                            .iter()
+                            .find(|entry| entry.file == file && entry.modified == modified)
+                        {
+                            Some(entry) => entry.clone(),
+                            None => ManifestEntry {
+                                file,
+                                modified,
+                                difficulty: rate(&path)?,
+                                duplicate_of: None,
+                            },
+                        },
+                    )
+                })
+                .collect(),
+        };
+        manifest.sort();
+        Ok(manifest)
+    }
+
+    /// The levels in `dir` with what the saved manifest knows about them,
+    /// without rating anything. New or changed levels are listed unrated.
+    pub fn cached(dir: &Path) -> Result<Self> {
+        Self::list(dir, |_| Some(None))
+    }
+
+    /// Runs [`Manifest::refresh`] on its own thread.
+    pub fn refresh_in_background(dir: PathBuf) -> Refresh {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let shared = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            let _ = sender.send(Self::refresh(&dir, &shared));
+        });
+        Refresh {
+            receiver,
+            cancelled,
+        }
+    }
+
+    /// Easiest first, unrated levels last.
+    fn sort(&mut self) {
+        self.levels.sort_by_key(|entry| {
+            entry
+                .difficulty
+                .map_or(u32::MAX, |difficulty| difficulty.score)
+        });
+    }
+
+    /// The manifest of the pack in `dir`, brought up to date with the levels
+    /// actually in it and saved back. New or changed levels get rated, and
+    /// the levels are ordered from easiest to hardest with unrated ones last.
+    /// Once `cancelled` is set no more levels are rated, and the ones that
+    /// weren't are left out until the next refresh.
+    fn refresh(dir: &Path, cancelled: &AtomicBool) -> Result<Self> {
+        let is_cancelled = || cancelled.load(Ordering::Relaxed);
+        let mut manifest = Self::list(dir, |path| {
+            let difficulty = levels::load(path)
+                .ok()
+                .and_then(|board| Difficulty::estimate_until(&board, is_cancelled));
+            (!is_cancelled()).then_some(difficulty)
+        })?;
+        let mut seen: HashMap<Board, String> = HashMap::new();
+        for entry in &mut manifest.levels {
+            let Ok(board) = levels::load(&entry.path(dir)) else {
+                continue;
+            };
+            let first = seen
+                .entry(board.normalized())
+                .or_insert_with(|| entry.file.clone());
+            entry.duplicate_of = (*first != entry.file).then(|| first.clone());
+        }
+        let content = ron::ser::to_string_pretty(&manifest, PrettyConfig::default())?;
+        let _writing = WRITING.lock().unwrap_or_else(PoisonError::into_inner);
+        fs::write(dir.join(MANIFEST), content)?;
+        Ok(manifest)
+    }
+}
@@ -0,0 +1,251 @@
+use std::{cell::Cell, path::Path, time::Duration};
+
+use anyhow::Result;
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Layout, Rect},
+    widgets::{Block, StatefulWidget, Widget, WidgetRef},
+};
+
+use crate::{
+    board::{frame_size, Board, Direction, Pos},
+    levels,
+    menu::{Menu, MenuOptions},
+    solver::Step,
+    theme,
+};
+
+/// Time between two moves when a solve is played back.
+const REPLAY_STEP: Duration = Duration::from_millis(150);
+
+pub struct PlayMenu {
+    name: String,
+    start: Board,
+    board: Board,
+    /// When playing in reverse, the layout the player has to get back to.
+    unsolve_to: Option<Board>,
+    active_player: usize,
+    history: Vec<Step>,
+    /// Time spent on the level, counted from the first move until it's won.
+    time: Duration,
+    /// While playing back the solve, how many moves are done and the time
+    /// since the last one.
+    replay: Option<(usize, Duration)>,
+    /// Top left tile in view, follows the active player on maps bigger than
+    /// the screen.
+    offset: Pos,
+    /// Where the map was last drawn, to know how much of it fits.
+    map_area: Cell<Rect>,
+}
+
+impl PlayMenu {
+    pub fn new(name: String, board: Board) -> Self {
+        Self {
+            name,
+            start: board.clone(),
+            board,
+            unsolve_to: None,
+            active_player: 0,
+            history: Vec::new(),
+            time: Duration::ZERO,
+            replay: None,
+            offset: Pos(0, 0),
+            map_area: Cell::default(),
+        }
+    }
+
+    /// Plays a level file, named after its title if it has one.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let file = levels::load_file(path)?;
+        let name = match file.metadata.title.as_str() {
+            "" => levels::name(path).to_owned(),
+            title => title.to_owned(),
+        };
+        Ok(Self::new(name, file.board))
+    }
+
+    /// Starts from the solved level and has the player get back to the
+    /// starting layout, moving boxes the opposite way the level does.
+    pub fn reversed(name: String, board: Board) -> Option<Self> {
+        let mut solved = board.solved()?;
+        solved.set_movement(board.movement().reversed());
+        Some(Self {
+            unsolve_to: Some(board),
+            ..Self::new(name, solved)
+        })
+    }
+
+    fn is_won(&self) -> bool {
+        match &self.unsolve_to {
+            Some(unsolved) => self.board.same_tiles(unsolved),
+            None => self.board.is_won(),
+        }
+    }
+
+    fn restart(&mut self) {
+        self.board = self.start.clone();
+        self.active_player = 0;
+        self.history.clear();
+        self.time = Duration::ZERO;
+        self.replay = None;
+    }
+
+    fn step(&mut self, direction: Direction) {
+        if !self.is_won() && self.board.try_move(self.active_player, direction) {
+            self.history.push((self.active_player, direction));
+        }
+    }
+
+    /// Scrolls just far enough for the given player to be in view.
+    fn scroll_to_player(&mut self, player: usize) {
+        let Some(&player) = self.board.players().get(player) else {
+            return;
+        };
+        let area = self.map_area.get();
+        let (width, height) = (
+            (area.width / theme::current().tile_width).max(1) as usize,
+            area.height.max(1) as usize,
+        );
+        self.offset = Pos(
+            self.offset
+                .0
+                .min(player.0)
+                .max((player.0 + 1).saturating_sub(width)),
+            self.offset
+                .1
+                .min(player.1)
+                .max((player.1 + 1).saturating_sub(height)),
+        );
+    }
+
+    /// Plays the moves that won the level again from the start.
+    fn start_replay(&mut self) {
+        if self.is_won() {
+            self.board = self.start.clone();
+            self.replay = Some((0, Duration::ZERO));
+        }
+    }
+}
+
+impl Menu for PlayMenu {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        if self.replay.is_some() {
+            return;
+        }
+        match input.code {
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'w') => self.step(Direction::Up),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'a') => self.step(Direction::Left),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'s') => self.step(Direction::Down),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'d') => self.step(Direction::Right),
+            KeyCode::Up => self.step(Direction::Up),
+            KeyCode::Left => self.step(Direction::Left),
+            KeyCode::Down => self.step(Direction::Down),
+            KeyCode::Right => self.step(Direction::Right),
+            KeyCode::Tab => {
+                self.active_player = (self.active_player + 1) % self.board.players().len().max(1)
+            }
+            KeyCode::BackTab => {
+                let players = self.board.players().len().max(1);
+                self.active_player = (self.active_player + players - 1) % players
+            }
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'r') => self.restart(),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'v') => self.start_replay(),
+            _ => {}
+        }
+        self.scroll_to_player(self.active_player);
+    }
+
+    fn tick(&mut self, elapsed: Duration) {
+        let playing = !self.history.is_empty() && !self.is_won();
+        match &mut self.replay {
+            Some((done, since_last)) => {
+                *since_last += elapsed;
+                if *since_last < REPLAY_STEP {
+                    return;
+                }
+                *since_last = Duration::ZERO;
+                match self.history.get(*done) {
+                    Some(&(player, direction)) => {
+                        *done += 1;
+                        self.board.try_move(player, direction);
+                        self.scroll_to_player(player);
+                    }
+                    None => self.replay = None,
+                }
+            }
+            None if playing => self.time += elapsed,
+            None => {}
+        }
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("WASD, arrows", "Move"),
+            ("Tab, Shift+Tab", "Switch player"),
+            ("R", "Restart"),
+            ("V", "Replay the solve"),
+        ]
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        None
+    }
+}
+
+impl WidgetRef for PlayMenu {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let theme = theme::current();
+        let buf_area = Layout::vertical([
+            Constraint::Max(frame_size(self.board.height())),
+            Constraint::Fill(1),
+        ])
+        .split(
+            Layout::horizontal([
+                Constraint::Max(frame_size(
+                    self.board.width().saturating_mul(theme.tile_width as usize),
+                )),
+                Constraint::Fill(1),
+            ])
+            .split(area)[0],
+        )[0];
+        let b = Block::bordered()
+            .title(if self.unsolve_to.is_some() {
+                format!("{} (reverse)", self.name)
+            } else {
+                self.name.clone()
+            })
+            .title_bottom(match (self.replay, self.is_won()) {
+                (Some((done, _)), _) => format!("Replay {}/{}", done, self.history.len()),
+                (None, true) => format!(
+                    "Solved in {} moves, {}:{:02} (V to replay)",
+                    self.history.len(),
+                    self.time.as_secs() / 60,
+                    self.time.as_secs() % 60
+                ),
+                (None, false) => format!(
+                    "Moves: {} {}:{:02}",
+                    self.history.len(),
+                    self.time.as_secs() / 60,
+                    self.time.as_secs() % 60
+                ),
+            });
+        let inner = b.inner(buf_area);
+        self.map_area.set(inner);
+        let mut offset = self.offset;
+        self.board.render(inner, buf, &mut offset);
+        if self.board.players().len() > 1 {
+            if let Some(tile_area) = self
+                .board
+                .players()
+                .get(self.active_player)
+                .and_then(|player| player.to_screen(offset, inner, theme.tile_width))
+            {
+                theme.active_player.apply(buf, tile_area);
+            }
+        }
+        b.render(buf_area, buf);
+    }
+}
@@ -1,12 +1,32 @@
-use ratatui::{buffer::Buffer, crossterm::event::KeyCode, layout::Rect, text::{Line, Text}, widgets::{Block, Padding, Paragraph, Widget, WidgetRef}};
+use ratatui::{buffer::Buffer, crossterm::event::KeyCode, layout::Rect, text::{Line, Text}, widgets::{Widget, WidgetRef}};
 
-use crate::menu::Menu;
+use crate::menu::{Menu, Popup};
 
-#[derive(Default, Clone, Copy)]
-pub enum QuitMenu {
-    #[default] Undecided,
-    Yes,
-    No,
+/// Asks whether to quit, listing the maps with changes that would be lost.
+pub struct QuitMenu {
+    unsaved: Vec<String>,
+    answer: Option<bool>,
+}
+
+impl QuitMenu {
+    pub fn new(unsaved: Vec<String>) -> Self {
+        Self{
+            unsaved,
+            answer: None,
+        }
+    }
+
+    fn text(&self) -> Text<'_>{
+        let mut lines = vec![Line::from("Are you sure you want to quit?")];
+        if !self.unsaved.is_empty(){
+            lines.push(Line::from(""));
+            lines.push(Line::from("These maps have unsaved changes:"));
+            lines.extend(self.unsaved.iter().map(|name| Line::from(name.as_str())));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("(Y)es   (N)o"));
+        Text::from(lines).centered()
+    }
 }
 
 impl Menu for QuitMenu {
@@ -14,42 +34,31 @@ impl Menu for QuitMenu {
 
     fn handle_input(&mut self, input: ratatui::crossterm::event::KeyEvent) {
         match input.code{
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'y') => *self = QuitMenu::Yes,
-            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'n') => *self = QuitMenu::No,
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'y') => self.answer = Some(true),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'n') => self.answer = Some(false),
             _ => {}
         }
     }
 
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Y", "Quit"), ("N", "Keep playing")]
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting("Quit", &self.text()))
+    }
+
     fn is_done(&mut self) -> Option<crate::menu::MenuOptions<Self::Output>> {
-        match self{
-            QuitMenu::Undecided => None,
-            QuitMenu::Yes => Some(crate::menu::MenuOptions::Exit(())),
-            QuitMenu::No => Some(crate::menu::MenuOptions::GoBack),
+        match self.answer{
+            None => None,
+            Some(true) => Some(crate::menu::MenuOptions::Exit(())),
+            Some(false) => Some(crate::menu::MenuOptions::GoBack),
         }
     }
 }
 
 impl WidgetRef for QuitMenu {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let text = Text::from(vec![
-            Line::from("Are you sure you want to quit?"),
-            Line::from(""),
-            Line::from("(Y)es   (N)o"),
-        ])
-        .centered();
-        let block = Block::bordered()
-            .title_alignment(ratatui::layout::Alignment::Center)
-            .title_top("Tank Game")
-            .padding(Padding::new(
-                0,                                        // left
-                0,                                        // right
-                (area.height - text.height() as u16) / 2, // top
-                0,                                        // bottom
-            ))
-            .border_type(ratatui::widgets::BorderType::Rounded);
-        Paragraph::new(text)
-            .centered()
-            .block(block)
-            .render(area, buf);
+        self.text().render(area, buf);
     }
 }
@@ -0,0 +1,91 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, TryLockError},
+};
+
+use anyhow::Result;
+use directories::ProjectDirs;
+use ratatui::crossterm::{event::DisableMouseCapture, execute};
+use ron::ser::PrettyConfig;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::levels::{self, LevelFile};
+
+/// File in the cache directory holding the map being edited, for when the
+/// game doesn't get to close normally.
+pub const RECOVERY: &str = "recovery.ron";
+
+static LATEST: Mutex<Option<Recovery>> = Mutex::new(None);
+
+/// A map being edited, and the file it belongs to if it was ever saved.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recovery {
+    pub path: Option<PathBuf>,
+    pub file: LevelFile,
+}
+
+fn recovery_path() -> Result<PathBuf> {
+    Ok(levels::game_dir(ProjectDirs::cache_dir)?.join(RECOVERY))
+}
+
+/// Doesn't wait for the lock, so a panic while it's held can't hang the
+/// panic hook.
+fn latest() -> Option<MutexGuard<'static, Option<Recovery>>> {
+    match LATEST.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Remembers the state of the map being edited, to be written out by the
+/// next [`flush`].
+pub fn record(recovery: Recovery) {
+    if let Some(mut latest) = latest() {
+        *latest = Some(recovery);
+    }
+}
+
+/// Writes what was last recorded to the recovery file.
+pub fn flush() -> Result<()> {
+    let Some(latest) = latest() else {
+        return Ok(());
+    };
+    if let Some(recovery) = latest.as_ref() {
+        fs::write(
+            recovery_path()?,
+            ron::ser::to_string_pretty(recovery, PrettyConfig::default())?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Forgets the recorded map and removes the recovery file, once there's
+/// nothing left to lose.
+pub fn clear() {
+    if let Some(mut latest) = latest() {
+        *latest = None;
+    }
+    if let Ok(path) = recovery_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// What was left in the recovery file by a session that didn't end normally.
+pub fn load() -> Option<Recovery> {
+    let content = fs::read_to_string(recovery_path().ok()?).ok()?;
+    ron::from_str(&content).ok()
+}
+
+/// Makes a panic put the terminal back the way it was and write out the map
+/// being edited before the panic message is printed.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        ratatui::restore();
+        let _ = flush();
+        previous(info);
+    }));
+}
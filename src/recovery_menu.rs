@@ -0,0 +1,102 @@
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    text::{Line, Text},
+    widgets::{Widget, WidgetRef},
+};
+
+use crate::{
+    edit_menu::EditMenu,
+    levels,
+    menu::{Menu, MenuOptions, Popup},
+    recovery::{self, Recovery},
+};
+
+/// Shown on launch when the last session left a map behind in the recovery
+/// file, asking whether to keep editing it.
+pub struct RecoveryMenu {
+    recovered: Option<Recovery>,
+    answer: Option<bool>,
+}
+
+impl RecoveryMenu {
+    pub fn new(recovered: Recovery) -> Self {
+        Self {
+            recovered: Some(recovered),
+            answer: None,
+        }
+    }
+
+    /// Name of the recovered map, `None` once the question is answered.
+    fn name(&self) -> Option<&str> {
+        let recovered = self.recovered.as_ref()?;
+        Some(recovered.path.as_deref().map_or("*Unsaved", levels::name))
+    }
+
+    fn text(&self) -> Text<'_> {
+        Text::from(vec![
+            Line::from("The game didn't close normally last time."),
+            Line::from(format!(
+                "Restore the changes to {}?",
+                self.name().unwrap_or_default()
+            )),
+            Line::from(""),
+            Line::from("(Y)es   (N)o"),
+        ])
+        .centered()
+    }
+}
+
+impl Menu for RecoveryMenu {
+    type Output = ();
+
+    fn handle_input(&mut self, input: KeyEvent) {
+        match input.code {
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'y') => self.answer = Some(true),
+            KeyCode::Char(x) if x.eq_ignore_ascii_case(&'n') => self.answer = Some(false),
+            _ => {}
+        }
+    }
+
+    /// Same as answering no, so the question doesn't come back next launch.
+    fn back(&mut self) -> bool {
+        self.answer = Some(false);
+        false
+    }
+
+    fn bindings(&self) -> &'static [(&'static str, &'static str)] {
+        &[("Y", "Restore the map"), ("N", "Throw it away")]
+    }
+
+    /// Quitting now would throw the recovered map away unasked.
+    fn unsaved_changes(&self) -> Option<&str> {
+        self.name()
+    }
+
+    fn popup(&self) -> Option<Popup<'_>> {
+        Some(Popup::fitting("Recovery", &self.text()))
+    }
+
+    fn is_done(&mut self) -> Option<MenuOptions<Self::Output>> {
+        match self.answer.take() {
+            Some(true) => Some(match self.recovered.take() {
+                Some(recovered) => MenuOptions::Continue(Box::new(EditMenu::recovered(recovered))),
+                None => MenuOptions::GoBack,
+            }),
+            Some(false) => {
+                recovery::clear();
+                Some(MenuOptions::GoBack)
+            }
+            // Back from the restored editor, so there's nothing left to ask.
+            None if self.recovered.is_none() => Some(MenuOptions::GoBack),
+            None => None,
+        }
+    }
+}
+
+impl WidgetRef for RecoveryMenu {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.text().render(area, buf);
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    board::{Board, Direction, Pos},
+    tiles::Tile,
+};
+
+/// A single move of the given player.
+pub type Step = (usize, Direction);
+
+/// A shortest sequence of moves that wins a level, in the order they're made,
+/// along with how hard the search had to work for it.
+pub struct Solution {
+    pub moves: Vec<Step>,
+    /// How many of the moves pushed or pulled a box.
+    pub pushes: usize,
+    /// How many boards were expanded before the solution was found.
+    pub nodes: usize,
+    /// Average number of new boards each expanded board led to.
+    pub branching: f32,
+}
+
+/// The part of a board that moves, the players and the boxes. Walls,
+/// targets and goals are the same on every board the search reaches, so
+/// this is all it keeps of them.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    players: Box<[Pos]>,
+    /// Row by row, so the same boxes always make the same state.
+    boxes: Box<[(Pos, Tile)]>,
+}
+
+impl State {
+    fn of(board: &Board) -> Self {
+        Self {
+            players: board.players().into(),
+            boxes: board.boxes().collect(),
+        }
+    }
+
+    /// Turns `board`, which currently shows `shown`, into this state.
+    fn show(&self, board: &mut Board, shown: &State) {
+        for (pos, _) in shown.boxes.iter() {
+            board.set_tile(*pos, Tile::Empty);
+        }
+        for (pos, tile) in self.boxes.iter() {
+            board.set_tile(*pos, *tile);
+        }
+        board.set_players(self.players.to_vec());
+    }
+
+    /// The state of `board` after a move from this state that started at
+    /// `from`. A move only changes boxes on the row or column it's made
+    /// along, so only that line is looked at again.
+    fn after_move(&self, board: &Board, from: Pos, direction: Direction) -> Self {
+        let vertical = matches!(direction, Direction::Up | Direction::Down);
+        let on_line = |pos: &Pos| {
+            if vertical {
+                pos.0 == from.0
+            } else {
+                pos.1 == from.1
+            }
+        };
+        let line: Vec<Pos> = if vertical {
+            (0..board.height()).map(|y| Pos(from.0, y)).collect()
+        } else {
+            (0..board.width()).map(|x| Pos(x, from.1)).collect()
+        };
+        let mut boxes: Vec<(Pos, Tile)> = self
+            .boxes
+            .iter()
+            .filter(|(pos, _)| !on_line(pos))
+            .copied()
+            .chain(
+                line.into_iter()
+                    .filter_map(|pos| Some((pos, board.box_at(pos)?))),
+            )
+            .collect();
+        boxes.sort_unstable_by_key(|(pos, _)| (pos.1, pos.0));
+        Self {
+            players: board.players().into(),
+            boxes: boxes.into(),
+        }
+    }
+}
+
+/// Breadth first search over every reachable board, giving up after looking
+/// at `max_nodes` of them or as soon as `stop` returns true, which is asked
+/// once for every board expanded. `None` means the level is unsolvable or too
+/// big to tell.
+pub fn solve_until(board: &Board, max_nodes: usize, stop: impl Fn() -> bool) -> Option<Solution> {
+    // Only the start is kept as a whole board, every other board is a
+    // `State` shown on it while it's expanded. Each queued state carries its
+    // index in `parents`, which holds the index of the state it was reached
+    // from, the move, and whether the move pushed a box.
+    let mut work = board.clone();
+    let mut shown = State::of(board);
+    let mut seen = HashSet::from([shown.clone()]);
+    let mut parents: Vec<Option<(usize, Step, bool)>> = vec![None];
+    let mut queue = VecDeque::from([(shown.clone(), 0)]);
+    let mut nodes = 0;
+    while let Some((state, index)) = queue.pop_front() {
+        state.show(&mut work, &shown);
+        shown = state;
+        if work.is_won() {
+            let mut moves = Vec::new();
+            let mut pushes = 0;
+            let mut current = index;
+            while let Some((parent, step, pushed)) = parents[current] {
+                moves.push(step);
+                pushes += usize::from(pushed);
+                current = parent;
+            }
+            moves.reverse();
+            return Some(Solution {
+                moves,
+                pushes,
+                nodes,
+                branching: (parents.len() - 1) as f32 / nodes.max(1) as f32,
+            });
+        }
+        nodes += 1;
+        if nodes > max_nodes || stop() {
+            return None;
+        }
+        for player in 0..shown.players.len() {
+            for direction in Direction::ALL {
+                if !work.try_move(player, direction) {
+                    continue;
+                }
+                let next = shown.after_move(&work, shown.players[player], direction);
+                shown.show(&mut work, &next);
+                if !seen.contains(&next) {
+                    seen.insert(next.clone());
+                    let pushed = next.boxes != shown.boxes;
+                    parents.push(Some((index, (player, direction), pushed)));
+                    queue.push_back((next, parents.len() - 1));
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,377 @@
+use std::{cell::RefCell, fs, rc::Rc};
+
+use anyhow::{bail, Context, Result};
+use directories::ProjectDirs;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier},
+    text::Span,
+};
+use ron::extensions::Extensions;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    levels,
+    tiles::{BoxColor, Tile},
+};
+
+/// File in the config directory picking the theme.
+pub const CONFIG: &str = "theme.ron";
+
+thread_local! {
+    static THEME: RefCell<Rc<Theme>> = RefCell::new(Rc::new(Theme::default()));
+}
+
+/// How something on the board is drawn. Anything left out keeps what was
+/// drawn under it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Glyph {
+    pub symbol: Option<String>,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+impl Glyph {
+    fn new(symbol: &str) -> Self {
+        Self {
+            symbol: Some(symbol.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn fg(self, fg: Color) -> Self {
+        Self {
+            fg: Some(fg),
+            ..self
+        }
+    }
+
+    fn bg(self, bg: Color) -> Self {
+        Self {
+            bg: Some(bg),
+            ..self
+        }
+    }
+
+    /// Draws the glyph over a tile's cells. A symbol made of single column
+    /// characters is spread over the cells one character each, anything
+    /// wider, like an emoji, goes in the first cell and covers the rest.
+    pub fn apply(&self, buf: &mut Buffer, area: Rect) {
+        let mut parts = self.symbol.as_deref().map(|symbol| {
+            if Span::raw(symbol).width() == symbol.chars().count() {
+                symbol
+                    .char_indices()
+                    .map(|(i, c)| &symbol[i..i + c.len_utf8()])
+                    .collect()
+            } else {
+                vec![symbol]
+            }
+            .into_iter()
+        });
+        for x in area.left()..area.right() {
+            let Some(cell) = buf.cell_mut((x, area.y)) else {
+                continue;
+            };
+            if let Some(parts) = &mut parts {
+                cell.set_symbol(parts.next().unwrap_or(" "));
+            }
+            if let Some(fg) = self.fg {
+                cell.set_fg(fg);
+            }
+            if let Some(bg) = self.bg {
+                cell.set_bg(bg);
+            }
+        }
+    }
+}
+
+/// One of something for each box color.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PerColor<T> {
+    pub red: T,
+    pub green: T,
+    pub blue: T,
+    pub yellow: T,
+}
+
+impl<T> PerColor<T> {
+    pub fn get(&self, color: BoxColor) -> &T {
+        match color {
+            BoxColor::Red => &self.red,
+            BoxColor::Green => &self.green,
+            BoxColor::Blue => &self.blue,
+            BoxColor::Yellow => &self.yellow,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Preset {
+    Ascii,
+    Unicode,
+    HighContrast,
+    Wide,
+    Emoji,
+}
+
+/// What `theme.ron` holds, either one of the built in themes, like
+/// `Preset(Unicode)`, or a theme of its own, like
+/// `Custom((wall: (symbol: "X", fg: "#808080"), cursor: "LightRed"))`. A
+/// custom theme only has to list what it changes from the ASCII one.
+#[derive(Deserialize)]
+enum ThemeConfig {
+    Preset(Preset),
+    Custom(Box<Theme>),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Columns every tile takes up. Terminal cells are about twice as tall as
+    /// they are wide, so tiles two columns wide come out square.
+    pub tile_width: u16,
+    pub empty: Glyph,
+    pub wall: Glyph,
+    #[serde(rename = "box")]
+    pub box_: Glyph,
+    pub heavy_box: Glyph,
+    pub chain_box: Glyph,
+    pub colored_boxes: PerColor<Glyph>,
+    /// Drawn on empty target tiles.
+    pub target: Glyph,
+    pub colored_targets: PerColor<Glyph>,
+    /// Added to every target tile, so filled ones still stand out.
+    pub target_modifier: Modifier,
+    pub player: Glyph,
+    /// Marks the player being moved when there are several.
+    pub active_player: Glyph,
+    pub goal: Glyph,
+    pub cursor: Color,
+    pub selection: Color,
+    pub preview: Color,
+}
+
+impl Theme {
+    pub fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Ascii => Self::ascii(),
+            Preset::Unicode => Self::unicode(),
+            Preset::HighContrast => Self::high_contrast(),
+            Preset::Wide => Self::wide(),
+            Preset::Emoji => Self::emoji(),
+        }
+    }
+
+    pub fn ascii() -> Self {
+        let colored_box = |color| Glyph::new("*").fg(color);
+        let colored_target = |color| Glyph::new(".").fg(color);
+        Self {
+            tile_width: 1,
+            empty: Glyph::new(" ").bg(Color::DarkGray),
+            wall: Glyph::new("#"),
+            box_: Glyph::new("*"),
+            heavy_box: Glyph::new("%"),
+            chain_box: Glyph::new("&"),
+            colored_boxes: PerColor {
+                red: colored_box(Color::Red),
+                green: colored_box(Color::Green),
+                blue: colored_box(Color::Blue),
+                yellow: colored_box(Color::Yellow),
+            },
+            target: Glyph::new(".").fg(Color::White),
+            colored_targets: PerColor {
+                red: colored_target(Color::Red),
+                green: colored_target(Color::Green),
+                blue: colored_target(Color::Blue),
+                yellow: colored_target(Color::Yellow),
+            },
+            target_modifier: Modifier::UNDERLINED,
+            player: Glyph::new("@"),
+            active_player: Glyph::default().fg(Color::Yellow),
+            goal: Glyph::default().bg(Color::Green),
+            cursor: Color::Blue,
+            selection: Color::Cyan,
+            preview: Color::Magenta,
+        }
+    }
+
+    /// Box drawing and geometric shapes instead of punctuation.
+    pub fn unicode() -> Self {
+        let ascii = Self::ascii();
+        let colored_box = |color| Glyph::new("■").fg(color);
+        Self {
+            wall: Glyph::new("█").fg(Color::Gray).bg(Color::DarkGray),
+            box_: Glyph::new("■"),
+            heavy_box: Glyph::new("▣"),
+            chain_box: Glyph::new("▤"),
+            colored_boxes: PerColor {
+                red: colored_box(Color::Red),
+                green: colored_box(Color::Green),
+                blue: colored_box(Color::Blue),
+                yellow: colored_box(Color::Yellow),
+            },
+            target: Glyph::new("·").fg(Color::White),
+            colored_targets: PerColor {
+                red: Glyph::new("·").fg(Color::Red),
+                green: Glyph::new("·").fg(Color::Green),
+                blue: Glyph::new("·").fg(Color::Blue),
+                yellow: Glyph::new("·").fg(Color::Yellow),
+            },
+            player: Glyph::new("☺"),
+            ..ascii
+        }
+    }
+
+    /// Black background and the Okabe-Ito palette, with colored boxes and
+    /// targets also told apart by letter so color is never the only hint.
+    pub fn high_contrast() -> Self {
+        const ORANGE: Color = Color::Rgb(230, 159, 0);
+        const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+        const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+        const YELLOW: Color = Color::Rgb(240, 228, 66);
+        const BLUE: Color = Color::Rgb(0, 114, 178);
+        const VERMILLION: Color = Color::Rgb(213, 94, 0);
+        Self {
+            tile_width: 1,
+            empty: Glyph::new(" ").bg(Color::Black),
+            wall: Glyph::new("#").fg(Color::Black).bg(Color::White),
+            box_: Glyph::new("*").fg(Color::White).bg(Color::Black),
+            heavy_box: Glyph::new("%").fg(Color::White).bg(Color::Black),
+            chain_box: Glyph::new("&").fg(Color::White).bg(Color::Black),
+            colored_boxes: PerColor {
+                red: Glyph::new("R").fg(VERMILLION),
+                green: Glyph::new("G").fg(BLUISH_GREEN),
+                blue: Glyph::new("B").fg(SKY_BLUE),
+                yellow: Glyph::new("Y").fg(YELLOW),
+            },
+            target: Glyph::new(".").fg(Color::White),
+            colored_targets: PerColor {
+                red: Glyph::new("r").fg(VERMILLION),
+                green: Glyph::new("g").fg(BLUISH_GREEN),
+                blue: Glyph::new("b").fg(SKY_BLUE),
+                yellow: Glyph::new("y").fg(YELLOW),
+            },
+            target_modifier: Modifier::UNDERLINED | Modifier::BOLD,
+            player: Glyph::new("@").fg(Color::White),
+            active_player: Glyph::default().fg(ORANGE),
+            goal: Glyph::default().bg(BLUE),
+            cursor: ORANGE,
+            selection: SKY_BLUE,
+            preview: VERMILLION,
+        }
+    }
+
+    /// Square tiles two columns wide.
+    pub fn wide() -> Self {
+        let ascii = Self::ascii();
+        let colored_box = |color| Glyph::new("[]").fg(color);
+        let colored_target = |color| Glyph::new("()").fg(color);
+        Self {
+            tile_width: 2,
+            empty: Glyph::new("  ").bg(Color::DarkGray),
+            wall: Glyph::new("██").fg(Color::Gray),
+            box_: Glyph::new("[]"),
+            heavy_box: Glyph::new("{}"),
+            chain_box: Glyph::new("<>"),
+            colored_boxes: PerColor {
+                red: colored_box(Color::Red),
+                green: colored_box(Color::Green),
+                blue: colored_box(Color::Blue),
+                yellow: colored_box(Color::Yellow),
+            },
+            target: Glyph::new("()").fg(Color::White),
+            colored_targets: PerColor {
+                red: colored_target(Color::Red),
+                green: colored_target(Color::Green),
+                blue: colored_target(Color::Blue),
+                yellow: colored_target(Color::Yellow),
+            },
+            player: Glyph::new("@@"),
+            ..ascii
+        }
+    }
+
+    /// Emoji are two columns wide in most terminals, so this is a wide theme
+    /// too.
+    pub fn emoji() -> Self {
+        let wide = Self::wide();
+        Self {
+            wall: Glyph::new("🧱"),
+            box_: Glyph::new("📦"),
+            heavy_box: Glyph::new("🪨"),
+            chain_box: Glyph::new("🔗"),
+            colored_boxes: PerColor {
+                red: Glyph::new("🟥"),
+                green: Glyph::new("🟩"),
+                blue: Glyph::new("🟦"),
+                yellow: Glyph::new("🟨"),
+            },
+            target: Glyph::new("🎯"),
+            colored_targets: PerColor {
+                red: Glyph::new("🔴"),
+                green: Glyph::new("🟢"),
+                blue: Glyph::new("🔵"),
+                yellow: Glyph::new("🟡"),
+            },
+            player: Glyph::new("🙂"),
+            ..wide
+        }
+    }
+
+    pub fn tile(&self, tile: &Tile) -> &Glyph {
+        match tile {
+            Tile::Empty => &self.empty,
+            Tile::Wall => &self.wall,
+            Tile::Box => &self.box_,
+            Tile::HeavyBox => &self.heavy_box,
+            Tile::ChainBox => &self.chain_box,
+            Tile::ColoredBox(color) => self.colored_boxes.get(*color),
+        }
+    }
+
+    pub fn target(&self, color: Option<BoxColor>) -> &Glyph {
+        color.map_or(&self.target, |color| self.colored_targets.get(color))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::ascii()
+    }
+}
+
+/// Reads the theme from the config directory and uses it from now on. A
+/// missing file keeps the default theme, a `tile_width` of 0 is an error.
+pub fn load() -> Result<()> {
+    let path = levels::game_dir(ProjectDirs::config_dir)?.join(CONFIG);
+    if !path.exists() {
+        return Ok(());
+    }
+    let config: ThemeConfig = ron::Options::default()
+        .with_default_extension(Extensions::IMPLICIT_SOME)
+        .from_str(&fs::read_to_string(&path)?)
+        .with_context(|| format!("invalid theme in {}", path.display()))?;
+    let theme = match config {
+        ThemeConfig::Preset(preset) => Theme::preset(preset),
+        ThemeConfig::Custom(theme) => *theme,
+    };
+    if theme.tile_width == 0 {
+        bail!(
+            "invalid theme in {}: tile_width has to be at least 1",
+            path.display()
+        );
+    }
+    set(theme);
+    Ok(())
+}
+
+pub fn set(theme: Theme) {
+    THEME.with_borrow_mut(|current| *current = Rc::new(theme));
+}
+
+/// The theme in use, shared so drawing doesn't have to copy it every frame.
+pub fn current() -> Rc<Theme> {
+    THEME.with_borrow(Rc::clone)
+}
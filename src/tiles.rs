@@ -1,43 +1,143 @@
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Serialize, Deserialize, Default, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, Debug)]
 pub enum Tile{
     #[default]
     Empty,
     Wall,
     Box,
+    HeavyBox,
+    ChainBox,
+    ColoredBox(BoxColor),
 }
 
-impl From<&Tile> for &str{
-    fn from(value: &Tile) -> Self {
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum BoxColor{
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+/// How a tile is written in a saved map's rows.
+impl From<Tile> for char{
+    fn from(value: Tile) -> Self {
         match value{
-            Tile::Wall => "#",
-            Tile::Box => "*",
-            Tile::Empty => " ",
+            Tile::Empty => '-',
+            Tile::Wall => '#',
+            Tile::Box => '*',
+            Tile::HeavyBox => '%',
+            Tile::ChainBox => '&',
+            Tile::ColoredBox(BoxColor::Red) => 'R',
+            Tile::ColoredBox(BoxColor::Green) => 'G',
+            Tile::ColoredBox(BoxColor::Blue) => 'B',
+            Tile::ColoredBox(BoxColor::Yellow) => 'Y',
         }
     }
 }
 
+impl TryFrom<char> for Tile{
+    type Error = char;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value{
+            '-' => Tile::Empty,
+            '#' => Tile::Wall,
+            '*' => Tile::Box,
+            '%' => Tile::HeavyBox,
+            '&' => Tile::ChainBox,
+            'R' => Tile::ColoredBox(BoxColor::Red),
+            'G' => Tile::ColoredBox(BoxColor::Green),
+            'B' => Tile::ColoredBox(BoxColor::Blue),
+            'Y' => Tile::ColoredBox(BoxColor::Yellow),
+            _ => return Err(value),
+        })
+    }
+}
+
 pub struct TileDescriptor{
     solid: bool,
     pushable: bool,
+    heavy: bool,
+    chains: bool,
+    color: Option<BoxColor>,
 }
 
 impl From<&Tile> for &'static TileDescriptor{
     fn from(value: &Tile) -> Self {
+        const BOX: TileDescriptor = TileDescriptor{
+            solid: true,
+            pushable: true,
+            heavy: false,
+            chains: false,
+            color: None,
+        };
         match value{
             Tile::Wall => &TileDescriptor{
                 solid: true,
                 pushable: false,
+                ..BOX
             },
-            Tile::Box => &TileDescriptor{
-                solid: true,
-                pushable: true,
+            Tile::Box => &BOX,
+            Tile::HeavyBox => &TileDescriptor{
+                heavy: true,
+                ..BOX
+            },
+            Tile::ChainBox => &TileDescriptor{
+                chains: true,
+                ..BOX
+            },
+            Tile::ColoredBox(BoxColor::Red) => &TileDescriptor{
+                color: Some(BoxColor::Red),
+                ..BOX
+            },
+            Tile::ColoredBox(BoxColor::Green) => &TileDescriptor{
+                color: Some(BoxColor::Green),
+                ..BOX
+            },
+            Tile::ColoredBox(BoxColor::Blue) => &TileDescriptor{
+                color: Some(BoxColor::Blue),
+                ..BOX
+            },
+            Tile::ColoredBox(BoxColor::Yellow) => &TileDescriptor{
+                color: Some(BoxColor::Yellow),
+                ..BOX
             },
             Tile::Empty => &TileDescriptor{
                 solid: false,
-                pushable: true, 
+                pushable: true,
+                ..BOX
             },
         }
     }
 }
+
+impl TileDescriptor{
+    pub fn is_solid(&self) -> bool{
+        self.solid
+    }
+
+    /// Only meaningful for solid tiles, walking onto a non solid tile never
+    /// pushes it.
+    pub fn is_pushable(&self) -> bool{
+        self.pushable
+    }
+
+    /// Heavy tiles can only be pushed by a player, never by another box.
+    pub fn is_heavy(&self) -> bool{
+        self.heavy
+    }
+
+    /// Chaining tiles push whatever box is in front of them along with them.
+    pub fn chains(&self) -> bool{
+        self.chains
+    }
+
+    pub fn color(&self) -> Option<BoxColor>{
+        self.color
+    }
+
+    pub fn is_box(&self) -> bool{
+        self.solid && self.pushable
+    }
+}
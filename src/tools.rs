@@ -1,24 +1,56 @@
 use ratatui::{
-    crossterm::cursor::position,
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Position, Rect},
     text::{Line, Text},
     widgets::{StatefulWidget, Widget},
 };
 
-use crate::tiles::Tile;
+use crate::{
+    board::{Pos, Transform},
+    tiles::{BoxColor, Tile},
+};
 
 #[derive(Debug)]
 pub enum Tool {
     Tile(Tile),
+    Player,
+    Goal,
+    Target(Option<BoxColor>),
+    WinCondition,
+    Movement,
     AreaTool(AreaTool),
+    Paste,
+    Transform(Transform),
+    Shape(Shape),
+    Bucket,
+    AutoWall,
     Save,
 }
 
+impl Tool {
+    /// Whether the tool does its thing without needing a place on the map,
+    /// so picking it from the command palette uses it right away.
+    pub fn is_instant(&self) -> bool {
+        matches!(
+            self,
+            Tool::WinCondition | Tool::Movement | Tool::Transform(_) | Tool::AutoWall | Tool::Save
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Line,
+    Rectangle,
+    FilledRectangle,
+}
+
 #[derive(Debug)]
 pub enum AreaTool {
     Move,
     Delete,
     Fill,
+    Copy,
+    Cut,
 }
 
 #[derive(Debug)]
@@ -43,6 +75,50 @@ impl MenuLayer {
                     MenuValue::Terminal(Tool::Tile(Tile::Empty)),
                     MenuValue::Terminal(Tool::Tile(Tile::Wall)),
                     MenuValue::Terminal(Tool::Tile(Tile::Box)),
+                    MenuValue::Terminal(Tool::Tile(Tile::HeavyBox)),
+                    MenuValue::Terminal(Tool::Tile(Tile::ChainBox)),
+                    MenuValue::Nested(MenuLayer {
+                        name: "Colored Boxes",
+                        sub_menu: &[
+                            MenuValue::Terminal(Tool::Tile(Tile::ColoredBox(BoxColor::Red))),
+                            MenuValue::Terminal(Tool::Tile(Tile::ColoredBox(BoxColor::Green))),
+                            MenuValue::Terminal(Tool::Tile(Tile::ColoredBox(BoxColor::Blue))),
+                            MenuValue::Terminal(Tool::Tile(Tile::ColoredBox(BoxColor::Yellow))),
+                        ],
+                    }),
+                ],
+            }),
+            MenuValue::Nested(MenuLayer {
+                name: "Markers",
+                sub_menu: &[
+                    MenuValue::Terminal(Tool::Player),
+                    MenuValue::Terminal(Tool::Goal),
+                    MenuValue::Terminal(Tool::Target(None)),
+                    MenuValue::Nested(MenuLayer {
+                        name: "Colored Targets",
+                        sub_menu: &[
+                            MenuValue::Terminal(Tool::Target(Some(BoxColor::Red))),
+                            MenuValue::Terminal(Tool::Target(Some(BoxColor::Green))),
+                            MenuValue::Terminal(Tool::Target(Some(BoxColor::Blue))),
+                            MenuValue::Terminal(Tool::Target(Some(BoxColor::Yellow))),
+                        ],
+                    }),
+                ],
+            }),
+            MenuValue::Nested(MenuLayer {
+                name: "Rules",
+                sub_menu: &[
+                    MenuValue::Terminal(Tool::WinCondition),
+                    MenuValue::Terminal(Tool::Movement),
+                ],
+            }),
+            MenuValue::Nested(MenuLayer {
+                name: "Draw",
+                sub_menu: &[
+                    MenuValue::Terminal(Tool::Shape(Shape::Line)),
+                    MenuValue::Terminal(Tool::Shape(Shape::Rectangle)),
+                    MenuValue::Terminal(Tool::Shape(Shape::FilledRectangle)),
+                    MenuValue::Terminal(Tool::Bucket),
                 ],
             }),
             MenuValue::Nested(MenuLayer {
@@ -51,13 +127,67 @@ impl MenuLayer {
                     MenuValue::Terminal(Tool::AreaTool(AreaTool::Move)),
                     MenuValue::Terminal(Tool::AreaTool(AreaTool::Delete)),
                     MenuValue::Terminal(Tool::AreaTool(AreaTool::Fill)),
+                    MenuValue::Terminal(Tool::AreaTool(AreaTool::Copy)),
+                    MenuValue::Terminal(Tool::AreaTool(AreaTool::Cut)),
+                    MenuValue::Terminal(Tool::Paste),
+                ],
+            }),
+            MenuValue::Nested(MenuLayer {
+                name: "Transform",
+                sub_menu: &[
+                    MenuValue::Terminal(Tool::Transform(Transform::Rotate90)),
+                    MenuValue::Terminal(Tool::Transform(Transform::Rotate180)),
+                    MenuValue::Terminal(Tool::Transform(Transform::Rotate270)),
+                    MenuValue::Terminal(Tool::Transform(Transform::FlipHorizontal)),
+                    MenuValue::Terminal(Tool::Transform(Transform::FlipVertical)),
                 ],
             }),
+            MenuValue::Terminal(Tool::AutoWall),
             MenuValue::Terminal(Tool::Save),
         ],
     });
 }
 
+impl Shape {
+    /// The cells the shape covers when drawn from `a` to `b`.
+    pub fn cells(self, a: Pos, b: Pos) -> Vec<Pos> {
+        let (left, right) = (a.0.min(b.0), a.0.max(b.0));
+        let (top, bottom) = (a.1.min(b.1), a.1.max(b.1));
+        match self {
+            Shape::Line => line(a, b),
+            Shape::Rectangle => (top..=bottom)
+                .flat_map(|y| (left..=right).map(move |x| Pos(x, y)))
+                .filter(|pos| pos.0 == left || pos.0 == right || pos.1 == top || pos.1 == bottom)
+                .collect(),
+            Shape::FilledRectangle => (top..=bottom)
+                .flat_map(|y| (left..=right).map(move |x| Pos(x, y)))
+                .collect(),
+        }
+    }
+}
+
+/// Bresenham's line from `a` to `b`, both ends included.
+fn line(a: Pos, b: Pos) -> Vec<Pos> {
+    let (mut x, mut y) = (a.0 as isize, a.1 as isize);
+    let (end_x, end_y) = (b.0 as isize, b.1 as isize);
+    let (dx, dy) = ((end_x - x).abs(), -(end_y - y).abs());
+    let (step_x, step_y) = ((end_x - x).signum(), (end_y - y).signum());
+    let mut error = dx + dy;
+    let mut cells = vec![a];
+    while (x, y) != (end_x, end_y) {
+        if 2 * error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if 2 * error <= dx {
+            error += dx;
+            y += step_y;
+        }
+        cells.push(Pos(x as usize, y as usize));
+    }
+    cells
+}
+
 impl MenuValue {
     pub fn name(&self) -> &str {
         match self {
@@ -66,12 +196,73 @@ impl MenuValue {
         }
     }
 
-    pub fn get_value(&self, position: &Vec<usize>) -> Option<&MenuValue> {
+    pub fn get_value(&self, position: &[usize]) -> Option<&MenuValue> {
         position.iter().try_fold(self, |val, pos| match val {
             MenuValue::Terminal(_) => Some(val),
             MenuValue::Nested(layer) => layer.sub_menu.get(*pos),
         })
     }
+
+    /// Every tool under this entry with the path to it, in menu order.
+    pub fn terminals(&self) -> Vec<(Vec<usize>, &MenuValue)> {
+        match self {
+            MenuValue::Terminal(_) => vec![(Vec::new(), self)],
+            MenuValue::Nested(layer) => layer
+                .sub_menu
+                .iter()
+                .enumerate()
+                .flat_map(|(i, value)| {
+                    value.terminals().into_iter().map(move |(mut path, value)| {
+                        path.insert(0, i);
+                        (path, value)
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl MenuLayer {
+    /// The area the entries of this layer go in, and the area left over for
+    /// the open nested layer.
+    fn split(&self, area: Rect) -> (Rect, Rect) {
+        let max_len: usize = self
+            .sub_menu
+            .iter()
+            .map(|val| val.name().len())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let lay = Layout::horizontal([Constraint::Fill(1), Constraint::Length(max_len as u16)])
+            .split(area);
+        (lay[1], lay[0])
+    }
+
+    /// The path to the entry drawn at `position`, when the layer is rendered
+    /// in `area` with the tool at `selected` open.
+    pub fn entry_at(
+        &self,
+        area: Rect,
+        selected: &[usize],
+        depth: usize,
+        position: Position,
+    ) -> Option<Vec<usize>> {
+        let (entries, rest) = self.split(area);
+        if entries.contains(position) {
+            let row = (position.y - entries.y) as usize;
+            return (row < self.sub_menu.len()).then(|| {
+                let mut path = selected[..depth].to_vec();
+                path.push(row);
+                path
+            });
+        }
+        match self.sub_menu.get(*selected.get(depth)?)? {
+            MenuValue::Nested(layer) if selected.len() > depth + 1 => {
+                layer.entry_at(rest, selected, depth + 1, position)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl<'a> StatefulWidget for &'a MenuLayer {
@@ -85,23 +276,15 @@ impl<'a> StatefulWidget for &'a MenuLayer {
     ) where
         Self: Sized,
     {
-        let max_len: usize = self
-            .sub_menu
-            .iter()
-            .map(|val| val.name().len())
-            .max()
-            .unwrap_or(0)
-            + 1;
-        let lay = Layout::horizontal([Constraint::Fill(1), Constraint::Length(max_len as u16)])
-            .split(area);
+        let (entries, rest) = self.split(area);
         let text = Text::from_iter(self.sub_menu.iter().enumerate().map(|(i, val)| {
             Line::from_iter([if state.0[state.1] == i { ">" } else { " " }, val.name()])
         }));
-        text.render(lay[1], buf);
+        text.render(entries, buf);
         if let MenuValue::Nested(layer) = &self.sub_menu[state.0[state.1]] {
             if state.0.len() > state.1 + 1 {
                 state.1 += 1;
-                layer.render(lay[0], buf, state);
+                layer.render(rest, buf, state);
             }
         }
     }
@@ -114,12 +297,44 @@ impl From<&Tool> for &str {
                 Tile::Empty => "Empty",
                 Tile::Wall => "Wall",
                 Tile::Box => "Box",
+                Tile::HeavyBox => "Heavy Box",
+                Tile::ChainBox => "Chain Box",
+                Tile::ColoredBox(BoxColor::Red) => "Red Box",
+                Tile::ColoredBox(BoxColor::Green) => "Green Box",
+                Tile::ColoredBox(BoxColor::Blue) => "Blue Box",
+                Tile::ColoredBox(BoxColor::Yellow) => "Yellow Box",
             },
+            Tool::Player => "Player",
+            Tool::Goal => "Goal",
+            Tool::Target(None) => "Target",
+            Tool::Target(Some(BoxColor::Red)) => "Red Target",
+            Tool::Target(Some(BoxColor::Green)) => "Green Target",
+            Tool::Target(Some(BoxColor::Blue)) => "Blue Target",
+            Tool::Target(Some(BoxColor::Yellow)) => "Yellow Target",
+            Tool::WinCondition => "Win Rule",
+            Tool::Movement => "Move Rule",
             Tool::AreaTool(area_tool) => match area_tool {
                 AreaTool::Move => "Move",
                 AreaTool::Delete => "Delete",
                 AreaTool::Fill => "Fill",
+                AreaTool::Copy => "Copy",
+                AreaTool::Cut => "Cut",
+            },
+            Tool::Paste => "Paste",
+            Tool::Transform(transform) => match transform {
+                Transform::Rotate90 => "Rotate 90",
+                Transform::Rotate180 => "Rotate 180",
+                Transform::Rotate270 => "Rotate 270",
+                Transform::FlipHorizontal => "Flip Horizontal",
+                Transform::FlipVertical => "Flip Vertical",
+            },
+            Tool::Shape(shape) => match shape {
+                Shape::Line => "Line",
+                Shape::Rectangle => "Rectangle",
+                Shape::FilledRectangle => "Filled Rectangle",
             },
+            Tool::Bucket => "Bucket Fill",
+            Tool::AutoWall => "Auto Wall",
             Tool::Save => "Save",
         }
     }